@@ -0,0 +1,191 @@
+use crate::{to_num, Body, Operator, Packet};
+
+/// How an operator packet stores the size of its sub-packets
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LengthType {
+    /// Type 0, the total length of the sub-packets in 15 bits
+    Bits,
+    /// Type 1, the number of sub-packets in 11 bits
+    Count,
+}
+
+/// Appends the lowest `len` bits of `num`, most significant first.
+fn push_num(bits: &mut Vec<u8>, num: u64, len: usize) {
+    for i in (0..len).rev() {
+        bits.push(((num >> i) & 1) as u8);
+    }
+}
+
+impl Packet {
+    /// Bits of the packet, all operators store their size with `length_type`.
+    pub fn encode(&self, length_type: LengthType) -> Result<Vec<u8>, String> {
+        let mut bits = Vec::new();
+        self.encode_into(&mut bits, length_type)?;
+        Ok(bits)
+    }
+
+    fn encode_into(&self, bits: &mut Vec<u8>, length_type: LengthType) -> Result<(), String> {
+        if self.version > 7 {
+            return Err(format!("Version {} does not fit into 3 bits", self.version));
+        }
+        push_num(bits, self.version as u64, 3);
+
+        let (op, sub_packets) = match &self.body {
+            Body::Literal(value) => {
+                push_num(bits, 4, 3);
+                // Groups of 4 bits, every group but the last starts with a 1
+                let groups = (64 - value.leading_zeros() as usize).div_ceil(4).max(1);
+                for group in (0..groups).rev() {
+                    bits.push((group > 0) as u8);
+                    push_num(bits, value >> (4 * group), 4);
+                }
+                return Ok(());
+            },
+            Body::Operator(op, sub_packets) => (op, sub_packets),
+        };
+        push_num(bits, op.id() as u64, 3);
+
+        let mut body = Vec::new();
+        for sub in sub_packets.iter() {
+            sub.encode_into(&mut body, length_type)?;
+        }
+        match length_type {
+            LengthType::Bits => {
+                if body.len() >= 1 << 15 {
+                    return Err(format!(
+                        "{} bits of sub-packets do not fit into 15 bits",
+                        body.len()
+                    ));
+                }
+                bits.push(0);
+                push_num(bits, body.len() as u64, 15);
+            },
+            LengthType::Count => {
+                if sub_packets.len() >= 1 << 11 {
+                    return Err(format!(
+                        "{} sub-packets do not fit into 11 bits",
+                        sub_packets.len()
+                    ));
+                }
+                bits.push(1);
+                push_num(bits, sub_packets.len() as u64, 11);
+            },
+        }
+        bits.extend(body);
+        Ok(())
+    }
+
+    /// The packet as a hex transmission.
+    pub fn to_hex(&self, length_type: LengthType) -> Result<String, String> {
+        Ok(to_hex(&self.encode(length_type)?))
+    }
+}
+
+/// Hex digits of the bits, padded with zeros to whole bytes like the puzzle transmissions.
+pub fn to_hex(bits: &[u8]) -> String {
+    let mut padded = bits.to_vec();
+    padded.resize(bits.len().div_ceil(8) * 8, 0);
+    padded
+        .chunks(4)
+        .map(|chunk| std::char::from_digit(to_num(chunk) as u32, 16).unwrap().to_ascii_uppercase())
+        .collect()
+}
+
+/// Parser for expressions like `sum(1, max@3(2, 7), 4)`.
+///
+/// Operators are `sum`, `product`, `min`, `max`, `gt`, `lt` and `eq`. Every number and operator can
+/// be followed by `@<version>`, the version defaults to 0.
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        let rest = &self.src[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Consumes `c` if it comes next.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.src[self.pos..].starts_with(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(format!("Expected '{}' at {}", c, self.pos))
+        }
+    }
+
+    fn word(&mut self) -> &'a str {
+        self.skip_whitespace();
+        let rest = &self.src[self.pos..];
+        let len = rest.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn expr(&mut self) -> Result<Packet, String> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let word = self.word();
+        if word.is_empty() {
+            return Err(format!("Expected a number or operator at {}", start));
+        }
+
+        let version = if self.eat('@') {
+            let version = self.word();
+            match version.parse::<u8>() {
+                Ok(v) if v < 8 => v,
+                _ => {
+                    return Err(format!(
+                        "Invalid version '{}' at {}",
+                        version,
+                        self.pos - version.len()
+                    ))
+                },
+            }
+        } else {
+            0
+        };
+
+        if let Ok(literal) = word.parse::<u64>() {
+            return Ok(Packet::literal(version, literal));
+        }
+
+        let op = match Operator::from_name(word) {
+            Some(op) => op,
+            None => return Err(format!("Unknown operator '{}' at {}", word, start)),
+        };
+        self.expect('(')?;
+        let mut sub_packets = vec![self.expr()?];
+        while self.eat(',') {
+            sub_packets.push(self.expr()?);
+        }
+        self.expect(')')?;
+
+        if op.is_comparison() && sub_packets.len() != 2 {
+            return Err(format!("'{}' takes 2 operands, got {}", word, sub_packets.len()));
+        }
+        Ok(Packet::operator(version, op, sub_packets))
+    }
+}
+
+/// Compiles an expression like `sum(1, max@3(2, 7), 4)` into a packet.
+pub fn compile(expr: &str) -> Result<Packet, String> {
+    let mut parser = Parser { src: expr, pos: 0 };
+    let packet = parser.expr()?;
+    parser.skip_whitespace();
+    if parser.pos != expr.len() {
+        return Err(format!("Unexpected input at {}", parser.pos));
+    }
+    Ok(packet)
+}
@@ -1,169 +1,317 @@
 use std::fs::File;
 use std::io::{BufRead, BufReader, Error};
 
+mod encode;
+
 const HEADER_LEN: usize = 6;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operator {
+    Sum,
+    Product,
+    Minimum,
+    Maximum,
+    GreaterThan,
+    LessThan,
+    EqualTo,
+}
+
+impl Operator {
+    /// Operator of a packet type ID, `None` for literals
+    fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Operator::Sum),
+            1 => Some(Operator::Product),
+            2 => Some(Operator::Minimum),
+            3 => Some(Operator::Maximum),
+            5 => Some(Operator::GreaterThan),
+            6 => Some(Operator::LessThan),
+            7 => Some(Operator::EqualTo),
+            _ => None,
+        }
+    }
+
+    fn id(&self) -> u8 {
+        match self {
+            Operator::Sum => 0,
+            Operator::Product => 1,
+            Operator::Minimum => 2,
+            Operator::Maximum => 3,
+            Operator::GreaterThan => 5,
+            Operator::LessThan => 6,
+            Operator::EqualTo => 7,
+        }
+    }
+
+    /// Name of the operator in function notation
+    fn name(&self) -> &'static str {
+        match self {
+            Operator::Sum => "sum",
+            Operator::Product => "product",
+            Operator::Minimum => "min",
+            Operator::Maximum => "max",
+            Operator::GreaterThan => "gt",
+            Operator::LessThan => "lt",
+            Operator::EqualTo => "eq",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        (0..8).filter_map(Operator::from_id).find(|op| op.name() == name)
+    }
+
+    fn is_comparison(&self) -> bool {
+        matches!(self, Operator::GreaterThan | Operator::LessThan | Operator::EqualTo)
+    }
+
+    /// Symbol and binding strength in infix notation, `None` for operators written as functions
+    fn infix(&self) -> Option<(&'static str, u8)> {
+        match self {
+            Operator::Sum => Some(("+", 2)),
+            Operator::Product => Some(("*", 3)),
+            Operator::GreaterThan => Some((">", 1)),
+            Operator::LessThan => Some(("<", 1)),
+            Operator::EqualTo => Some(("==", 1)),
+            Operator::Minimum | Operator::Maximum => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Body {
+    Literal(u64),
+    Operator(Operator, Vec<Packet>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 struct Packet {
     version: u8,
-    id: u8,
-    literal: u64,
-    sub_packets: Vec<Packet>,
+    body: Body,
 }
 
 impl Packet {
-    fn from(bits: &[u8]) -> Self {
-        let (packet, _) = build_packet(&bits).unwrap();
-        packet
+    fn literal(version: u8, value: u64) -> Self {
+        Packet { version, body: Body::Literal(value) }
+    }
+
+    fn operator(version: u8, op: Operator, sub_packets: Vec<Packet>) -> Self {
+        Packet { version, body: Body::Operator(op, sub_packets) }
+    }
+
+    /// Decodes a whole transmission, only zeros may follow the outermost packet.
+    fn decode(bits: &[u8]) -> Result<Self, DecodeError> {
+        let (packet, end) = build_packet(bits, 0)?;
+        if let Some(pos) = bits[end..].iter().position(|&bit| bit != 0) {
+            return Err(DecodeError::TrailingData { pos: end + pos });
+        }
+        Ok(packet)
+    }
+
+    fn sub_packets(&self) -> &[Packet] {
+        match &self.body {
+            Body::Literal(_) => &[],
+            Body::Operator(_, sub_packets) => sub_packets,
+        }
     }
 
     fn version_sum(&self) -> u64 {
         let mut sum = self.version as u64;
-        for sub in self.sub_packets.iter() {
+        for sub in self.sub_packets().iter() {
             sum += sub.version_sum();
         }
         sum
     }
 
     fn evaluate(&self) -> u64 {
-        match self.id {
-            4 => {
-                // Literal
-                return self.literal;
+        let (op, sub_packets) = match &self.body {
+            Body::Literal(value) => return *value,
+            Body::Operator(op, sub_packets) => (op, sub_packets),
+        };
+        let mut values = sub_packets.iter().map(|packet| packet.evaluate());
+        match op {
+            Operator::Sum => values.sum(),
+            Operator::Product => values.product(),
+            Operator::Minimum => values.min().unwrap_or(u64::MAX),
+            Operator::Maximum => values.max().unwrap_or(u64::MIN),
+            Operator::GreaterThan => (values.next() > values.next()) as u64,
+            Operator::LessThan => (values.next() < values.next()) as u64,
+            Operator::EqualTo => (values.next() == values.next()) as u64,
+        }
+    }
+
+    /// Binding strength of the packet in infix notation, everything but infix operators is atomic
+    fn precedence(&self) -> u8 {
+        match &self.body {
+            Body::Operator(op, sub_packets) if sub_packets.len() >= 2 => {
+                op.infix().map_or(u8::MAX, |(_, precedence)| precedence)
             },
-            0 => {
-                // Sum packet
-                let mut sum = 0;
-                for packet in self.sub_packets.iter() {
-                    sum += packet.evaluate();
+            _ => u8::MAX,
+        }
+    }
+}
+
+impl std::fmt::Display for Packet {
+    /// Infix expression like `(3 + 7) * min(2, 9)`, versions are left out.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (op, sub_packets) = match &self.body {
+            Body::Literal(value) => return write!(f, "{}", value),
+            Body::Operator(op, sub_packets) => (op, sub_packets),
+        };
+
+        let precedence = self.precedence();
+        match op.infix() {
+            Some((symbol, _)) if precedence != u8::MAX => {
+                for (i, sub) in sub_packets.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " {} ", symbol)?;
+                    }
+                    // Comparisons do not chain, so nested ones always keep their parentheses
+                    let sub_precedence = sub.precedence();
+                    let same = sub_precedence == precedence;
+                    if sub_precedence < precedence || (same && op.is_comparison()) {
+                        write!(f, "({})", sub)?;
+                    } else {
+                        write!(f, "{}", sub)?;
+                    }
                 }
-                return sum;
+                Ok(())
             },
-            1 => {
-                // Product packet
-                let mut prod = 1;
-                for packet in self.sub_packets.iter() {
-                    prod *= packet.evaluate();
+            _ => {
+                // Functions and infix operators without two operands
+                write!(f, "{}(", op.name())?;
+                for (i, sub) in sub_packets.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", sub)?;
                 }
-                return prod;
+                write!(f, ")")
             },
-            2 => {
-                // Minimum packet
-                let mut min = u64::MAX;
-                for packet in self.sub_packets.iter() {
-                    let val = packet.evaluate();
-                    min = std::cmp::min(val, min);
-                }
-                return min;
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum DecodeError {
+    /// The transmission ends at `pos` while reading `field`
+    Truncated { pos: usize, field: &'static str },
+    /// Length type bit at `pos` is neither 0 nor 1
+    BadLengthType { pos: usize, bit: u8 },
+    /// The literal at `pos` has more than 64 bits
+    LiteralOverflow { pos: usize },
+    /// The sub-packets of the operator at `pos` take `actual` bits instead of `declared`
+    LengthMismatch { pos: usize, declared: usize, actual: usize },
+    /// A comparison at `pos` has `count` sub-packets instead of 2
+    OperandCount { pos: usize, count: usize },
+    /// Non-zero bit at `pos` after the outermost packet
+    TrailingData { pos: usize },
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Truncated { pos, field } => {
+                write!(f, "Transmission ends at bit {} while reading the {}", pos, field)
             },
-            3 => {
-                // Maximum packet
-                let mut max = u64::MIN;
-                for packet in self.sub_packets.iter() {
-                    let val = packet.evaluate();
-                    max = std::cmp::max(val, max);
-                }
-                return max;
+            DecodeError::BadLengthType { pos, bit } => {
+                write!(f, "Invalid length type {} at bit {}", bit, pos)
             },
-            5 => {
-                // Greater-than packet
-                return (self.sub_packets[0].evaluate() > self.sub_packets[1].evaluate()) as u64;
+            DecodeError::LiteralOverflow { pos } => {
+                write!(f, "Literal at bit {} does not fit into 64 bits", pos)
             },
-            6 => {
-                // Less-than packet
-                return (self.sub_packets[0].evaluate() < self.sub_packets[1].evaluate()) as u64;
+            DecodeError::LengthMismatch { pos, declared, actual } => write!(
+                f,
+                "Sub-packets of the operator at bit {} take {} bits instead of {}",
+                pos, actual, declared
+            ),
+            DecodeError::OperandCount { pos, count } => write!(
+                f,
+                "Comparison at bit {} has {} sub-packets instead of 2",
+                pos, count
+            ),
+            DecodeError::TrailingData { pos } => {
+                write!(f, "Unexpected data at bit {} after the packet", pos)
             },
-            7 => {
-                // Equal-to packet
-                return (self.sub_packets[0].evaluate() == self.sub_packets[1].evaluate()) as u64;
-            }
-            _ => {
-                return 0;
-            }
         }
     }
 }
 
-fn build_packet(bits: &[u8]) -> Result<(Packet, usize), ()> {
-    if bits.len() < HEADER_LEN {
-        return Err(());
+/// Reads `len` bits at `pos` as a number.
+fn read(bits: &[u8], pos: usize, len: usize, field: &'static str) -> Result<u64, DecodeError> {
+    match bits.get(pos..pos + len) {
+        Some(slice) => Ok(to_num(slice)),
+        None => Err(DecodeError::Truncated { pos: bits.len(), field }),
     }
+}
 
-    let version = (bits[0] << 2) + (bits[1] << 1) + bits[2];
-    let id = (bits[3] << 2) + (bits[4] << 1) + bits[5];
+/// Decodes the packet starting at bit `start`, returns it and the position right after it.
+fn build_packet(bits: &[u8], start: usize) -> Result<(Packet, usize), DecodeError> {
+    let version = read(bits, start, 3, "packet version")? as u8;
+    let id = read(bits, start + 3, 3, "packet type ID")? as u8;
+    let mut ptr = start + HEADER_LEN;
 
-    let body = &bits[HEADER_LEN..];
-    match id {
-        4 => {
+    let op = match Operator::from_id(id) {
+        Some(op) => op,
+        None => {
             // Literal package - Read bundles of 5 bits until the first bit is zero
-            let mut literal = Vec::<u8>::new();
-
-            let mut ptr = 0;
-            while ptr <= body.len() - 5 {
+            let mut literal = 0_u64;
+            loop {
                 // Parse a block of 5 bits where the first bit indicats continuation status
-                literal.push(body[ptr + 1]);
-                literal.push(body[ptr + 2]);
-                literal.push(body[ptr + 3]);
-                literal.push(body[ptr + 4]);
-
-                if body[ptr] == 0 {
-                    ptr += 5;
-                    break;
+                let group = read(bits, ptr, 5, "literal")?;
+                if literal >> 60 != 0 {
+                    return Err(DecodeError::LiteralOverflow { pos: start });
                 }
+                literal = (literal << 4) | (group & 0xF);
                 ptr += 5;
+                if group >> 4 == 0 {
+                    break;
+                }
             }
-
-            let parsed_literal = to_num(&literal);
-            return Ok((
-                Packet { version, id, literal: parsed_literal, sub_packets: Vec::<Packet>::new() },
-                ptr + HEADER_LEN
-            ));
+            return Ok((Packet::literal(version, literal), ptr));
         },
-        _ => {
-            // Operator package containing sub-packages to be parsed separately
-            match body[0] {
-                0 => {
-                    if body.len() < 16 {
-                        return Err(());
-                    }
-
-                    // Next 15 bits are total length in bits of the sub-packets
-                    let sub_bits = to_num(&body[1..16]) as usize;
-                    let mut sub_packets = Vec::<Packet>::new();
-
-                    let mut ptr = 16_usize;
-                    while ptr < sub_bits + 16 {
-                        let (packet, end_bit) = build_packet(&body[ptr..])?;
-                        ptr += end_bit;
-                        sub_packets.push(packet);
-                    }
-
-                    return Ok((Packet { version, id, literal: 0_u64, sub_packets }, ptr + HEADER_LEN));
-                },
-                1 => {
-                    if body.len() < 12 {
-                        return Err(());
-                    }
-
-                    // Next 11 bits are total number of sub-packets immediately contained
-                    let sub_packet_num = to_num(&body[1..12]) as usize;
-                    let mut sub_packets = Vec::<Packet>::with_capacity(sub_packet_num);
+    };
 
-                    let mut ptr = 12_usize;
-                    for _ in 0..sub_packet_num {
-                        let (packet, end_bit) = build_packet(&body[ptr..])?;
-                        ptr += end_bit;
-                        sub_packets.push(packet);
-                    }
-
-                    return Ok((Packet { version, id, literal: 0_u64, sub_packets }, ptr + HEADER_LEN));
-                },
-                _ => {
-                    return Err(());
-                }
+    // Operator package containing sub-packages to be parsed separately
+    let length_type = read(bits, ptr, 1, "length type")?;
+    let mut sub_packets = Vec::<Packet>::new();
+    match length_type {
+        0 => {
+            // Next 15 bits are total length in bits of the sub-packets
+            let sub_bits = read(bits, ptr + 1, 15, "sub-packet length")? as usize;
+            ptr += 16;
+            let end = ptr + sub_bits;
+            while ptr < end {
+                let (packet, end_bit) = build_packet(bits, ptr)?;
+                ptr = end_bit;
+                sub_packets.push(packet);
             }
+            if ptr != end {
+                return Err(DecodeError::LengthMismatch {
+                    pos: start,
+                    declared: sub_bits,
+                    actual: sub_bits + ptr - end,
+                });
+            }
+        },
+        1 => {
+            // Next 11 bits are total number of sub-packets immediately contained
+            let sub_packet_num = read(bits, ptr + 1, 11, "sub-packet count")? as usize;
+            ptr += 12;
+            for _ in 0..sub_packet_num {
+                let (packet, end_bit) = build_packet(bits, ptr)?;
+                ptr = end_bit;
+                sub_packets.push(packet);
+            }
+        },
+        _ => {
+            return Err(DecodeError::BadLengthType { pos: ptr, bit: bits[ptr] });
+        },
+    }
 
-        }
+    if op.is_comparison() && sub_packets.len() != 2 {
+        return Err(DecodeError::OperandCount { pos: start, count: sub_packets.len() });
     }
+    Ok((Packet::operator(version, op, sub_packets), ptr))
 }
 
 fn to_num(bits: &[u8]) -> u64 {
@@ -178,20 +326,190 @@ fn parse_input(filename: &str) -> Result<Vec<u8>, Error> {
     let reader = BufReader::new(File::open(filename)?);
     let line = reader.lines().next().unwrap().unwrap();
 
+    Ok(from_hex(&line))
+}
+
+#[cfg(test)]
+fn from_bin(line: &str) -> Vec<u8> {
+    line.bytes().map(|b| b - b'0').collect()
+}
+
+fn from_hex(line: &str) -> Vec<u8> {
     let mut out = Vec::<u8>::with_capacity(line.len() * 4);
-    for c in line.chars() {
+    for c in line.trim().chars() {
         let x = c.to_digit(16).unwrap() as u8;
         out.extend([x >> 3, (x >> 2) & 1, (x >> 1) & 1, x & 1]);
     }
-
-    Ok(out)
+    out
 }
 
 fn main() {
+    // Building a transmission from an expression like `sum(1, max(2, 3))`, operators store the
+    // length of their sub-packets in bits unless `count` is given
+    if std::env::args().nth(1).as_deref() == Some("encode") {
+        let expr = std::env::args().nth(2).expect("No expression given");
+        let length_type = match std::env::args().nth(3).as_deref() {
+            Some("count") => encode::LengthType::Count,
+            _ => encode::LengthType::Bits,
+        };
+        let packet = encode::compile(&expr).expect("Invalid expression");
+        println!("{}", packet.to_hex(length_type).expect("Failed to encode packet"));
+        return;
+    }
+
     let input = parse_input("in.txt")
         .expect("Failed to parse input");
 
-    let packet = Packet::from(&input);
+    let packet = match Packet::decode(&input) {
+        Ok(packet) => packet,
+        Err(err) => panic!("Failed to decode packet: {}", err),
+    };
     println!("ONE: Version sum: {}", packet.version_sum());
     println!("TWO: Packet evaluates to {}", packet.evaluate());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encode::LengthType;
+
+    /// Xorshift generator, enough to come up with arbitrary packets
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+    }
+
+    fn arbitrary_packet(rng: &mut Rng, depth: usize) -> Packet {
+        let version = rng.below(8) as u8;
+        let op = match depth {
+            0 => None,
+            _ => Operator::from_id(rng.below(8) as u8),
+        };
+        let Some(op) = op else {
+            // Small values as well as values using all 64 bits
+            return Packet::literal(version, rng.next() >> rng.below(64));
+        };
+        let count = if op.is_comparison() { 2 } else { rng.below(4) as usize };
+        let sub_packets = (0..count).map(|_| arbitrary_packet(rng, depth - 1)).collect();
+        Packet::operator(version, op, sub_packets)
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut rng = Rng(0x2021_1216);
+        for _ in 0..500 {
+            let packet = arbitrary_packet(&mut rng, 4);
+            for length_type in [LengthType::Bits, LengthType::Count] {
+                let bits = packet.encode(length_type).unwrap();
+                assert_eq!(build_packet(&bits, 0).unwrap(), (packet.clone(), bits.len()));
+
+                let hex = packet.to_hex(length_type).unwrap();
+                assert_eq!(Packet::decode(&from_hex(&hex)).unwrap(), packet);
+            }
+        }
+    }
+
+    #[test]
+    fn encode_examples() {
+        let hex = |transmission: &str, length_type| {
+            Packet::decode(&from_hex(transmission)).unwrap().to_hex(length_type).unwrap()
+        };
+        assert_eq!(hex("D2FE28", LengthType::Bits), "D2FE28");
+        assert_eq!(hex("38006F45291200", LengthType::Bits), "38006F45291200");
+        assert_eq!(hex("EE00D40C823060", LengthType::Count), "EE00D40C823060");
+
+        let too_long = Packet::operator(0, Operator::Sum, vec![Packet::literal(0, 1); 2048]);
+        assert!(too_long.encode(LengthType::Bits).is_ok());
+        assert_eq!(
+            too_long.encode(LengthType::Count).unwrap_err(),
+            "2048 sub-packets do not fit into 11 bits"
+        );
+    }
+
+    #[test]
+    fn compile_expressions() {
+        let evaluate = |expr: &str| {
+            let hex = encode::compile(expr).unwrap().to_hex(LengthType::Bits).unwrap();
+            Packet::decode(&from_hex(&hex)).unwrap().evaluate()
+        };
+        assert_eq!(evaluate("sum(1, 2)"), 3);
+        assert_eq!(evaluate("product(6, 9)"), 54);
+        assert_eq!(evaluate("min(7, 8, 9)"), 7);
+        assert_eq!(evaluate("max(7, 8, 9)"), 9);
+        assert_eq!(evaluate("lt(5, 15)"), 1);
+        assert_eq!(evaluate("gt(5, 15)"), 0);
+        assert_eq!(evaluate("eq(sum(1, 3), product(2, 2))"), 1);
+
+        let packet = encode::compile("max@3(2@1, sum@5(7))").unwrap();
+        assert_eq!(packet.version_sum(), 9);
+        assert_eq!(packet.evaluate(), 7);
+
+        assert_eq!(encode::compile("gt(1)").unwrap_err(), "'gt' takes 2 operands, got 1");
+        assert_eq!(encode::compile("div(1, 2)").unwrap_err(), "Unknown operator 'div' at 0");
+        assert_eq!(encode::compile("sum(1@8)").unwrap_err(), "Invalid version '8' at 6");
+        assert_eq!(encode::compile("sum(1, )").unwrap_err(), "Expected a number or operator at 7");
+        assert_eq!(encode::compile("sum(1) 2").unwrap_err(), "Unexpected input at 7");
+    }
+
+    #[test]
+    fn decode_errors() {
+        let err = |transmission: &str| Packet::decode(&from_hex(transmission)).unwrap_err();
+        assert_eq!(
+            err("D2FE"),
+            DecodeError::Truncated { pos: 16, field: "literal" }
+        );
+        assert_eq!(
+            err("38006F452912").to_string(),
+            "Transmission ends at bit 48 while reading the literal"
+        );
+        // Literal 2021 followed by another packet
+        assert_eq!(err("D2FE28D2"), DecodeError::TrailingData { pos: 24 });
+        // The sum declares 10 bits of sub-packets but its literal takes 11
+        let bits = from_bin("0000000000000000001010000100000010");
+        assert_eq!(
+            Packet::decode(&bits).unwrap_err().to_string(),
+            "Sub-packets of the operator at bit 0 take 11 bits instead of 10"
+        );
+        // Greater-than with a single sub-packet
+        let bits = from_bin("000101100000000001000100000100000");
+        assert_eq!(
+            Packet::decode(&bits).unwrap_err(),
+            DecodeError::OperandCount { pos: 0, count: 1 }
+        );
+        // 17 groups of 4 bits
+        let literal = format!("000100{}00000", "11111".repeat(16));
+        assert_eq!(
+            Packet::decode(&from_bin(&literal)).unwrap_err(),
+            DecodeError::LiteralOverflow { pos: 0 }
+        );
+        let mut bits = from_hex("EE00D40C823060");
+        bits[6] = 2;
+        assert_eq!(
+            Packet::decode(&bits).unwrap_err().to_string(),
+            "Invalid length type 2 at bit 6"
+        );
+    }
+
+    #[test]
+    fn pretty_print() {
+        let print = |expr: &str| encode::compile(expr).unwrap().to_string();
+        assert_eq!(print("product(sum(3, 7), min(2, 9))"), "(3 + 7) * min(2, 9)");
+        assert_eq!(print("sum(1, product(2, 3), sum(4, 5))"), "1 + 2 * 3 + 4 + 5");
+        assert_eq!(print("lt(sum(1, 2), eq(3, 4))"), "1 + 2 < (3 == 4)");
+        assert_eq!(print("max(gt(product(1, 2), 3), sum(7))"), "max(1 * 2 > 3, sum(7))");
+
+        let packet = Packet::decode(&from_hex("9C0141080250320F1802104A08")).unwrap();
+        assert_eq!(packet.to_string(), "1 + 3 == 2 * 2");
+        assert_eq!(packet.evaluate(), 1);
+    }
+}
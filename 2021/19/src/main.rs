@@ -1,38 +1,316 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
+use aoc_common::geometry::{Rotation, Transform, Vec3};
 use regex::Regex;
 
-#[derive(Clone)]
-struct Coordinate {
-    x: i32,
-    y: i32,
-    z: i32,
-}
+/// Two scanners seeing the same 12 beacons share at least the distances between all pairs of them
+const MIN_OVERLAP: usize = 12;
+const MIN_SHARED_DISTANCES: usize = MIN_OVERLAP * (MIN_OVERLAP - 1) / 2;
 
-fn parse_input() -> VecDeque<Vec<Coordinate>> {
-    let lines = include_str!("../in.txt")
+fn parse_input(input: &str) -> VecDeque<Vec<Vec3>> {
+    let header = Regex::new(r"^---*").unwrap();
+    let lines = input
         .split("\n\n")
-        .filter(|line| *line != "")
-        .map(|scanner_line| -> Vec<Coordinate> {
+        .filter(|line| !line.is_empty())
+        .map(|scanner_line| -> Vec<Vec3> {
             scanner_line
                 .split("\n")
-                .filter(|line| !Regex::new(r"^---*").unwrap().is_match(line) && *line != "")
-                .map(|coord_str| -> Coordinate {
+                .filter(|line| !header.is_match(line) && !line.is_empty())
+                .map(|coord_str| -> Vec3 {
                     let mut coords = coord_str.split(',');
-                    Coordinate {
-                        x: coords.next().unwrap().parse::<i32>().unwrap(),
-                        y: coords.next().unwrap().parse::<i32>().unwrap(),
-                        z: coords.next().unwrap().parse::<i32>().unwrap(),
-                    }
+                    Vec3::new(
+                        coords.next().unwrap().parse::<i64>().unwrap(),
+                        coords.next().unwrap().parse::<i64>().unwrap(),
+                        coords.next().unwrap().parse::<i64>().unwrap(),
+                    )
                 })
-                .collect::<Vec<Coordinate>>()
+                .collect::<Vec<Vec3>>()
         })
-        .collect::<VecDeque<Vec<Coordinate>>>();
+        .collect::<VecDeque<Vec<Vec3>>>();
     lines
 }
 
+/// Squared distances between all pairs of beacons of one scanner with how often they occur.
+/// They do not depend on the orientation or position of the scanner.
+fn fingerprint(beacons: &[Vec3]) -> HashMap<i64, usize> {
+    let mut distances = HashMap::new();
+    for (idx, a) in beacons.iter().enumerate() {
+        for b in &beacons[idx + 1..] {
+            *distances.entry(a.dist_squared(*b)).or_insert(0) += 1;
+        }
+    }
+    distances
+}
+
+fn shared_distances(a: &HashMap<i64, usize>, b: &HashMap<i64, usize>) -> usize {
+    a.iter()
+        .map(|(dist, count)| b.get(dist).map_or(0, |other| *count.min(other)))
+        .sum()
+}
+
+/// Finds the transform mapping `beacons` into the coordinates of `known`.
+///
+/// For every rotation each pair of a known and a rotated beacon votes for the translation that
+/// would make them match, the scanners overlap if one translation gets enough votes.
+fn align(known: &[Vec3], beacons: &[Vec3]) -> Option<Transform> {
+    for rotation in Rotation::all() {
+        let mut votes = HashMap::new();
+        for b in beacons {
+            let rotated = rotation.apply(*b);
+            for a in known {
+                let count = votes.entry(*a - rotated).or_insert(0);
+                *count += 1;
+                if *count >= MIN_OVERLAP {
+                    return Some(Transform::new(rotation, *a - rotated));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Transforms of every scanner into the coordinates of the first one, `None` for scanners that do
+/// not overlap with any other.
+fn locate_scanners(scanners: &VecDeque<Vec<Vec3>>) -> Vec<Option<Transform>> {
+    let fingerprints = scanners
+        .iter()
+        .map(|beacons| fingerprint(beacons))
+        .collect::<Vec<_>>();
+
+    let mut transforms = vec![None; scanners.len()];
+    let mut absolute = vec![Vec::new(); scanners.len()];
+    let mut queue = VecDeque::new();
+    if let Some(first) = scanners.front() {
+        transforms[0] = Some(Transform::IDENTITY);
+        absolute[0] = first.clone();
+        queue.push_back(0);
+    }
+
+    while let Some(placed) = queue.pop_front() {
+        for other in 0..scanners.len() {
+            if transforms[other].is_some()
+                || shared_distances(&fingerprints[placed], &fingerprints[other])
+                    < MIN_SHARED_DISTANCES
+            {
+                continue;
+            }
+
+            if let Some(transform) = align(&absolute[placed], &scanners[other]) {
+                absolute[other] = transform.apply_all(&scanners[other]);
+                transforms[other] = Some(transform);
+                queue.push_back(other);
+            }
+        }
+    }
+
+    transforms
+}
+
+fn unique_beacons(scanners: &VecDeque<Vec<Vec3>>, transforms: &[Option<Transform>]) -> usize {
+    scanners
+        .iter()
+        .zip(transforms)
+        .filter_map(|(beacons, transform)| transform.map(|t| t.apply_all(beacons)))
+        .flatten()
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+fn largest_distance(transforms: &[Option<Transform>]) -> i64 {
+    let positions = transforms
+        .iter()
+        .flatten()
+        .map(|t| t.translation)
+        .collect::<Vec<_>>();
+    positions
+        .iter()
+        .flat_map(|a| positions.iter().map(|b| a.manhattan(*b)))
+        .max()
+        .unwrap_or(0)
+}
+
 fn main() {
-    let mut input = parse_input();
+    let input = parse_input(include_str!("../in.txt"));
+
+    let transforms = locate_scanners(&input);
+    if transforms.iter().any(|t| t.is_none()) {
+        panic!("Not every scanner overlaps with another one");
+    }
+
+    println!("ONE: Unique beacons = {}", unique_beacons(&input, &transforms));
+    println!("TWO: Largest scanner distance = {}", largest_distance(&transforms));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "--- scanner 0 ---
+404,-588,-901
+528,-643,409
+-838,591,734
+390,-675,-793
+-537,-823,-458
+-485,-357,347
+-345,-311,381
+-661,-816,-575
+-876,649,763
+-618,-824,-621
+553,345,-567
+474,580,667
+-447,-329,318
+-584,868,-557
+544,-627,-890
+564,392,-477
+455,729,728
+-892,524,684
+-689,845,-530
+423,-701,434
+7,-33,-71
+630,319,-379
+443,580,662
+-789,900,-551
+459,-707,401
+
+--- scanner 1 ---
+686,422,578
+605,423,415
+515,917,-361
+-336,658,858
+95,138,22
+-476,619,847
+-340,-569,-846
+567,-361,727
+-460,603,-452
+669,-402,600
+729,430,532
+-500,-761,534
+-322,571,750
+-466,-666,-811
+-429,-592,574
+-355,545,-477
+703,-491,-529
+-328,-685,520
+413,935,-424
+-391,539,-444
+586,-435,557
+-364,-763,-893
+807,-499,-711
+755,-354,-619
+553,889,-390
+
+--- scanner 2 ---
+649,640,665
+682,-795,504
+-784,533,-524
+-644,584,-595
+-588,-843,648
+-30,6,44
+-674,560,763
+500,723,-460
+609,671,-379
+-555,-800,653
+-675,-892,-343
+697,-426,-610
+578,704,681
+493,664,-388
+-671,-858,530
+-667,343,800
+571,-461,-707
+-138,-166,112
+-889,563,-600
+646,-828,498
+640,759,510
+-630,509,768
+-681,-892,-333
+673,-379,-804
+-742,-814,-386
+577,-820,562
+
+--- scanner 3 ---
+-589,542,597
+605,-692,669
+-500,565,-823
+-660,373,557
+-458,-679,-417
+-488,449,543
+-626,468,-788
+338,-750,-386
+528,-832,-391
+562,-778,733
+-938,-730,414
+543,643,-506
+-524,371,-870
+407,773,750
+-104,29,83
+378,-903,-323
+-778,-728,485
+426,699,580
+-438,-605,-362
+-469,-447,-387
+509,732,623
+647,635,-688
+-868,-804,481
+614,-800,639
+595,780,-596
+
+--- scanner 4 ---
+727,592,562
+-293,-554,779
+441,611,-461
+-714,465,-776
+-743,427,-804
+-660,-479,-426
+832,-632,460
+927,-485,-438
+408,393,-506
+466,436,-512
+110,16,151
+-258,-428,682
+-393,719,612
+-211,-452,876
+808,-476,-593
+-575,615,604
+-485,667,467
+-680,325,-822
+-627,-443,-432
+872,-547,-609
+833,512,582
+807,604,487
+839,-516,451
+891,-625,532
+-652,-548,-490
+30,-46,-14
+";
+
+    #[test]
+    fn scanner_positions() {
+        let transforms = locate_scanners(&parse_input(INPUT));
+        let positions = transforms
+            .iter()
+            .map(|t| t.map(|t| t.translation))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            positions,
+            vec![
+                Some(Vec3::ZERO),
+                Some(Vec3::new(68, -1246, -43)),
+                Some(Vec3::new(1105, -1205, 1229)),
+                Some(Vec3::new(-92, -2380, -20)),
+                Some(Vec3::new(-20, -1133, 1061)),
+            ]
+        );
+    }
+
+    #[test]
+    fn part_one() {
+        let scanners = parse_input(INPUT);
+        let transforms = locate_scanners(&scanners);
+        assert_eq!(unique_beacons(&scanners, &transforms), 79);
+    }
 
-    let known_orientation = vec![Coordinate { x: 0, y: 0, z: 0 }; 1];
-    let known_beacons = vec![input.pop_front().unwrap(); 1];
+    #[test]
+    fn part_two() {
+        let transforms = locate_scanners(&parse_input(INPUT));
+        assert_eq!(largest_distance(&transforms), 3621);
+    }
 }
@@ -0,0 +1,227 @@
+use crate::{Instruction, Op};
+
+/// Instruction with its operands resolved, registers are indices and constants are inlined.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Code {
+    Inp(u8),
+    /// `mul a 0` and an optional `add a <num>` right after it
+    Set(u8, i64),
+    /// `mul a 0` and `add a b`
+    Copy(u8, u8),
+    AddReg(u8, u8),
+    AddImm(u8, i64),
+    MulReg(u8, u8),
+    MulImm(u8, i64),
+    DivReg(u8, u8),
+    DivImm(u8, i64),
+    ModReg(u8, u8),
+    ModImm(u8, i64),
+    EqlReg(u8, u8),
+    EqlImm(u8, i64),
+    /// `eql a b` and `eql a 0`
+    NeqReg(u8, u8),
+    NeqImm(u8, i64),
+}
+
+impl Code {
+    /// Code for a single operation, `None` if it does not change anything.
+    fn lower(op: &Op) -> Result<Option<Self>, String> {
+        let a = op.op_a.to_index() as u8;
+        let reg = op.op_b.as_ref().map(|reg| reg.to_index() as u8);
+        let imm = op.op_b_num.unwrap_or(0);
+        Ok(Some(match (&op.inst, reg, imm) {
+            (Instruction::INP, _, _) => Code::Inp(a),
+            (Instruction::ADD, None, 0) | (Instruction::MUL | Instruction::DIV, None, 1) => {
+                return Ok(None)
+            }
+            (Instruction::ADD, Some(b), _) => Code::AddReg(a, b),
+            (Instruction::ADD, None, _) => Code::AddImm(a, imm),
+            (Instruction::MUL, Some(b), _) => Code::MulReg(a, b),
+            (Instruction::MUL, None, 0) => Code::Set(a, 0),
+            (Instruction::MUL, None, _) => Code::MulImm(a, imm),
+            (Instruction::DIV, Some(b), _) => Code::DivReg(a, b),
+            (Instruction::DIV, None, _) => Code::DivImm(a, imm),
+            (Instruction::MOD, Some(b), _) => Code::ModReg(a, b),
+            (Instruction::MOD, None, _) => Code::ModImm(a, imm),
+            (Instruction::EQL, Some(b), _) => Code::EqlReg(a, b),
+            (Instruction::EQL, None, _) => Code::EqlImm(a, imm),
+            (Instruction::NoOp, _, _) => return Err("Unknown instruction".to_string()),
+        }))
+    }
+
+    /// Single code doing the same as `self` followed by `next`.
+    fn fuse(self, next: Self) -> Option<Self> {
+        match (self, next) {
+            (Code::Set(a, value), Code::AddImm(b, num)) if a == b => {
+                Some(Code::Set(a, value + num))
+            }
+            (Code::Set(a, 0), Code::AddReg(b, src)) if a == b && a != src => {
+                Some(Code::Copy(a, src))
+            }
+            (Code::EqlReg(a, src), Code::EqlImm(b, 0)) if a == b => Some(Code::NeqReg(a, src)),
+            (Code::EqlImm(a, num), Code::EqlImm(b, 0)) if a == b => Some(Code::NeqImm(a, num)),
+            _ => None,
+        }
+    }
+}
+
+/// Program lowered to bytecode and split into blocks that each read one digit.
+#[derive(Clone, Debug)]
+pub struct Compiled {
+    code: Vec<Code>,
+    /// Address of every input in `code`
+    inputs: Vec<usize>,
+}
+
+impl Compiled {
+    pub fn new(ops: &[Op]) -> Result<Self, String> {
+        let mut code: Vec<Code> = Vec::with_capacity(ops.len());
+        let mut inputs = Vec::new();
+        for (idx, op) in ops.iter().enumerate() {
+            let Some(next) =
+                Code::lower(op).map_err(|err| format!("{} at line {}", err, idx + 1))?
+            else {
+                continue;
+            };
+            if let Some(fused) = code.last().and_then(|last| last.fuse(next)) {
+                *code.last_mut().unwrap() = fused;
+                continue;
+            }
+            if let Code::Inp(_) = next {
+                inputs.push(code.len());
+            }
+            code.push(next);
+        }
+        Ok(Self { code, inputs })
+    }
+
+    /// Number of blocks, every block reads one digit apart from the only block of a program
+    /// without inputs.
+    pub fn blocks(&self) -> usize {
+        self.inputs.len().max(1)
+    }
+
+    /// Runs block `block` reading `digit`, the first block also runs everything before the first
+    /// input.
+    pub fn run_block(&self, block: usize, regs: &mut [i64; 4], digit: i64) {
+        let start = if block == 0 { 0 } else { self.inputs[block] };
+        let end = self
+            .inputs
+            .get(block + 1)
+            .copied()
+            .unwrap_or(self.code.len());
+        // Masking the register indices lets the compiler drop the bounds checks
+        for code in &self.code[start..end] {
+            match *code {
+                Code::Inp(a) => regs[a as usize & 3] = digit,
+                Code::Set(a, value) => regs[a as usize & 3] = value,
+                Code::Copy(a, b) => regs[a as usize & 3] = regs[b as usize & 3],
+                Code::AddReg(a, b) => regs[a as usize & 3] += regs[b as usize & 3],
+                Code::AddImm(a, value) => regs[a as usize & 3] += value,
+                Code::MulReg(a, b) => regs[a as usize & 3] *= regs[b as usize & 3],
+                Code::MulImm(a, value) => regs[a as usize & 3] *= value,
+                Code::DivReg(a, b) => regs[a as usize & 3] /= regs[b as usize & 3],
+                Code::DivImm(a, value) => regs[a as usize & 3] /= value,
+                Code::ModReg(a, b) => regs[a as usize & 3] %= regs[b as usize & 3],
+                Code::ModImm(a, value) => regs[a as usize & 3] %= value,
+                Code::EqlReg(a, b) => {
+                    regs[a as usize & 3] = (regs[a as usize & 3] == regs[b as usize & 3]) as i64
+                }
+                Code::EqlImm(a, value) => {
+                    regs[a as usize & 3] = (regs[a as usize & 3] == value) as i64
+                }
+                Code::NeqReg(a, b) => {
+                    regs[a as usize & 3] = (regs[a as usize & 3] != regs[b as usize & 3]) as i64
+                }
+                Code::NeqImm(a, value) => {
+                    regs[a as usize & 3] = (regs[a as usize & 3] != value) as i64
+                }
+            }
+        }
+    }
+
+    /// Runs the whole program on `input` starting with all registers zero.
+    pub fn run(&self, input: &[i64]) -> [i64; 4] {
+        let mut regs = [0; 4];
+        for block in 0..self.blocks() {
+            self.run_block(
+                block,
+                &mut regs,
+                input.get(block).copied().unwrap_or_default(),
+            );
+        }
+        regs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Program;
+
+    fn ops(source: &str) -> Vec<Op> {
+        source.lines().map(Op::from).collect()
+    }
+
+    /// Registers after running `source` with the interpreter and compiled.
+    fn both(source: &str, input: &[i64]) -> ([i64; 4], [i64; 4]) {
+        let program = ops(source);
+        let mut interpreter = Program::from([0; 4], program.clone());
+        interpreter.run(input);
+        (
+            interpreter.registers,
+            Compiled::new(&program).unwrap().run(input),
+        )
+    }
+
+    #[test]
+    fn matches_interpreter() {
+        let source = "inp w\ninp x\ninp y\nadd z w\nmul z 7\nadd z -3\nmul z x\nmod z 5\n\
+                      add y z\ndiv y w\nmod y x\nmul x 0\nadd x y\neql x 4\neql w y\ndiv z 2\n\
+                      inp w\nmul w z\nadd w 11\neql z 0\nmul y 0\nadd y w\nmul x 0\nadd x x\n\
+                      eql y x\neql y 0";
+        for a in 1..10 {
+            for b in 1..10 {
+                for c in [1, 4, 9] {
+                    for d in [-20, 3, 17] {
+                        let (expected, compiled) = both(source, &[a, b, c, d]);
+                        assert_eq!(compiled, expected, "input {:?}", [a, b, c, d]);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fuses_operations() {
+        let program = ops(
+            "inp w\nmul x 0\nadd x z\nmul y 0\nadd y 25\ndiv z 1\neql x w\neql x 0\n\
+                           mul y 0\nadd y y\ninp x",
+        );
+        let compiled = Compiled::new(&program).unwrap();
+        assert_eq!(
+            compiled.code,
+            [
+                Code::Inp(0),
+                Code::Copy(1, 3),
+                Code::Set(2, 25),
+                Code::NeqReg(1, 0),
+                Code::Set(2, 0),
+                Code::AddReg(2, 2),
+                Code::Inp(1),
+            ]
+        );
+        assert_eq!(compiled.blocks(), 2);
+
+        let mut regs = [0, 0, 0, 7];
+        compiled.run_block(0, &mut regs, 7);
+        assert_eq!(regs, [7, 0, 0, 7]);
+        compiled.run_block(1, &mut regs, 4);
+        assert_eq!(regs, [7, 4, 0, 7]);
+
+        assert_eq!(
+            Compiled::new(&ops("inp w\nnop w")).unwrap_err(),
+            "Unknown instruction at line 2"
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use crate::{Instruction, Op};
+
+/// Closed range of values a register can hold.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Interval {
+    pub lo: i64,
+    pub hi: i64,
+}
+
+impl Interval {
+    pub fn new(lo: i64, hi: i64) -> Self {
+        Self { lo, hi }
+    }
+
+    pub fn constant(value: i64) -> Self {
+        Self::new(value, value)
+    }
+
+    pub fn contains(&self, value: i64) -> bool {
+        self.lo <= value && value <= self.hi
+    }
+
+    fn union(self, other: Self) -> Self {
+        Self::new(self.lo.min(other.lo), self.hi.max(other.hi))
+    }
+
+    /// Smallest interval holding all the values
+    fn hull(values: &[i64]) -> Self {
+        let lo = *values.iter().min().unwrap();
+        let hi = *values.iter().max().unwrap();
+        Self::new(lo, hi)
+    }
+
+    fn add(self, other: Self) -> Self {
+        Self::new(
+            self.lo.saturating_add(other.lo),
+            self.hi.saturating_add(other.hi),
+        )
+    }
+
+    fn mul(self, other: Self) -> Self {
+        Self::hull(&[
+            self.lo.saturating_mul(other.lo),
+            self.lo.saturating_mul(other.hi),
+            self.hi.saturating_mul(other.lo),
+            self.hi.saturating_mul(other.hi),
+        ])
+    }
+
+    /// Truncating division, dividing by zero is not allowed so a zero divisor is left out.
+    fn div(self, other: Self) -> Self {
+        // Without a sign change in the divisor the quotient is monotonic in both operands
+        let quotients = |divisor: Self| {
+            Self::hull(&[
+                self.lo.saturating_div(divisor.lo),
+                self.lo.saturating_div(divisor.hi),
+                self.hi.saturating_div(divisor.lo),
+                self.hi.saturating_div(divisor.hi),
+            ])
+        };
+        let negative = (other.lo < 0).then(|| Self::new(other.lo, other.hi.min(-1)));
+        let positive = (other.hi > 0).then(|| Self::new(other.lo.max(1), other.hi));
+        match (negative.map(quotients), positive.map(quotients)) {
+            (Some(neg), Some(pos)) => neg.union(pos),
+            (Some(result), None) | (None, Some(result)) => result,
+            // Division by zero, the program is invalid anyway
+            (None, None) => self,
+        }
+    }
+
+    /// Remainder with the sign of the dividend like `%`.
+    fn rem(self, other: Self) -> Self {
+        if self.lo >= 0 && other.lo > 0 && self.hi < other.lo {
+            return self;
+        }
+        let max_rem = other.lo.saturating_abs().max(other.hi.saturating_abs()) - 1;
+        let lo = if self.lo >= 0 {
+            0
+        } else {
+            self.lo.max(-max_rem)
+        };
+        let hi = if self.hi <= 0 {
+            0
+        } else {
+            self.hi.min(max_rem)
+        };
+        Self::new(lo, hi.max(lo))
+    }
+
+    fn eql(self, other: Self) -> Self {
+        if self.lo == self.hi && self == other {
+            Self::constant(1)
+        } else if self.hi < other.lo || other.hi < self.lo {
+            Self::constant(0)
+        } else {
+            Self::new(0, 1)
+        }
+    }
+}
+
+/// Ranges the registers can end up in when running `ops` from `pc` on, starting with the given
+/// registers and reading digits in `digits`.
+pub fn final_ranges(
+    ops: &[Op],
+    pc: usize,
+    registers: [Interval; 4],
+    digits: Interval,
+) -> [Interval; 4] {
+    let mut regs = registers;
+    for op in &ops[pc..] {
+        let a = op.op_a.to_index();
+        let b = match (&op.op_b, op.op_b_num) {
+            (Some(reg), _) => regs[reg.to_index()],
+            (None, Some(num)) => Interval::constant(num),
+            (None, None) => Interval::constant(0),
+        };
+        regs[a] = match op.inst {
+            Instruction::INP => digits,
+            Instruction::ADD => regs[a].add(b),
+            Instruction::MUL => regs[a].mul(b),
+            Instruction::DIV => regs[a].div(b),
+            Instruction::MOD => regs[a].rem(b),
+            Instruction::EQL => regs[a].eql(b),
+            Instruction::NoOp => regs[a],
+        };
+    }
+    regs
+}
+
+/// Whether the program can still end with `z == 0` if all the remaining digits are in `digits`.
+pub fn may_reach_zero(ops: &[Op], pc: usize, registers: [i64; 4], digits: Interval) -> bool {
+    let registers = registers.map(Interval::constant);
+    final_ranges(ops, pc, registers, digits)[3].contains(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Program;
+
+    fn ops(source: &str) -> Vec<Op> {
+        source.lines().map(Op::from).collect()
+    }
+
+    #[test]
+    fn contains_concrete_results() {
+        let values = -7..=7;
+        let ranges = [(-7, -2), (-3, 4), (0, 0), (1, 1), (2, 7), (-5, 5), (3, 3)];
+        for inst in ["add", "mul", "div", "mod", "eql"] {
+            for &(a_lo, a_hi) in &ranges {
+                for &(b_lo, b_hi) in &ranges {
+                    let program = ops(&format!("inp x\ninp y\n{} x y", inst));
+                    let mut regs = [Interval::constant(0); 4];
+                    regs[1] = Interval::new(a_lo, a_hi);
+                    regs[2] = Interval::new(b_lo, b_hi);
+                    let result = final_ranges(&program, 2, regs, Interval::constant(0))[1];
+                    for a in values.clone().filter(|a| regs[1].contains(*a)) {
+                        for b in values.clone().filter(|b| regs[2].contains(*b)) {
+                            let mut p = Program::from([0; 4], program.clone());
+                            if (inst == "div" || inst == "mod") && b == 0 {
+                                continue;
+                            }
+                            p.run(&[a, b]);
+                            assert!(
+                                result.contains(p.x()),
+                                "{} {} {} = {} not in {:?}",
+                                a,
+                                inst,
+                                b,
+                                p.x(),
+                                result
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn prunes_unreachable_zero() {
+        // The first digit is doubled and the last one can take at most 9 off again
+        let program = ops("inp z\ninp w\nmul z 2\nadd z w\ninp w\nmul w -1\nadd z w");
+        let digits = Interval::new(1, 9);
+        assert!(may_reach_zero(&program, 1, [0, 0, 0, 1], digits));
+        assert!(!may_reach_zero(&program, 1, [0, 0, 0, 9], digits));
+        assert!(may_reach_zero(
+            &program,
+            1,
+            [0, 0, 0, 9],
+            Interval::new(1, 20)
+        ));
+    }
+}
@@ -2,6 +2,12 @@ use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::collections::HashMap;
 
+use compile::Compiled;
+
+mod compile;
+mod interval;
+mod symbolic;
+
 #[derive(Clone, Eq, PartialEq)]
 enum Instruction {
     INP,
@@ -230,41 +236,69 @@ fn parse_input(filename: &str) -> Result<Program, std::io::Error> {
     Ok(Program::from([0; 4], ops))
 }
 
-fn get_valid_internal(program: &Program, visited: &mut HashMap<Program, Option<i64>>, number_range: &[i64]) -> Option<i64> {
-    if let Some(solution) = visited.get(&program) {
+/// Search state, the block reading the next digit and the registers before it
+type State = (usize, [i64; 4]);
+
+struct Search<'a> {
+    ops: &'a [Op],
+    code: &'a Compiled,
+    /// Address of every input in `ops`
+    inputs: Vec<usize>,
+    number_range: &'a [i64],
+    visited: HashMap<State, Option<i64>>,
+}
+
+fn get_valid_internal(search: &mut Search, state: State) -> Option<i64> {
+    if let Some(solution) = search.visited.get(&state) {
         return *solution;
     }
 
-    'input: for input in number_range {
-        let mut p = program.clone();
-        p.exec(Some(*input));
-
-        while !p.finished() {
-            if p.peek_op() == Instruction::INP {
-                if let Some(old_smallest) = get_valid_internal(&p, visited, number_range) {
-                    visited.insert(p.clone(), Some(old_smallest * 10 + *input));
-                    return Some(old_smallest * 10 + *input);
-                } else {
-                    continue 'input;
-                }
-            } else {
-                p.exec(None);
-            }
-        }
+    // Skip states that can not end with z == 0 whatever the remaining digits are
+    let (block, registers) = state;
+    let lo = *search.number_range.iter().min().unwrap();
+    let hi = *search.number_range.iter().max().unwrap();
+    let digits = interval::Interval::new(lo, hi);
+    // The first block also runs everything before the first input
+    let pc = if block == 0 { 0 } else { search.inputs[block] };
+    if !interval::may_reach_zero(search.ops, pc, registers, digits) {
+        search.visited.insert(state, None);
+        return None;
+    }
 
-        if p.z() == 0 {
-            visited.insert(p, Some(*input));
-            return Some(*input);
+    for input in search.number_range {
+        let mut regs = registers;
+        search.code.run_block(block, &mut regs, *input);
+        let solution = if block + 1 < search.code.blocks() {
+            get_valid_internal(search, (block + 1, regs)).map(|rest| rest * 10 + *input)
+        } else if regs[3] == 0 {
+            Some(*input)
+        } else {
+            None
+        };
+        if solution.is_some() {
+            search.visited.insert(state, solution);
+            return solution;
         }
     }
 
-    visited.insert(program.clone(), None);
+    search.visited.insert(state, None);
     None
 }
 
-fn biggest_valid(program: &Program) -> i64 {
-    let number_range = [9, 8, 7, 6, 5, 4, 3, 2, 1];
-    let mut tmp = get_valid_internal(&program, &mut HashMap::new(), &number_range).unwrap();
+fn search_valid(program: &Program, code: &Compiled, number_range: &[i64]) -> i64 {
+    let inputs = program.ops.iter()
+        .enumerate()
+        .filter(|(_, op)| op.inst == Instruction::INP)
+        .map(|(idx, _)| idx)
+        .collect();
+    let mut search = Search {
+        ops: &program.ops,
+        code,
+        inputs,
+        number_range,
+        visited: HashMap::new(),
+    };
+    let mut tmp = get_valid_internal(&mut search, (0, [0; 4])).unwrap();
     let mut reversed = 0;
     while tmp != 0 {
         reversed = reversed * 10 + tmp % 10;
@@ -273,24 +307,58 @@ fn biggest_valid(program: &Program) -> i64 {
     reversed
 }
 
-fn smallest_valid(program: &Program) -> i64 {
-    let number_range = [1, 2, 3, 4, 5, 6, 7, 8, 9];
-    let mut tmp = get_valid_internal(&program, &mut HashMap::new(), &number_range).unwrap();
-    let mut reversed = 0;
-    while tmp != 0 {
-        reversed = reversed * 10 + tmp % 10;
-        tmp /= 10;
+fn biggest_valid(program: &Program, code: &Compiled) -> i64 {
+    search_valid(program, code, &[9, 8, 7, 6, 5, 4, 3, 2, 1])
+}
+
+fn smallest_valid(program: &Program, code: &Compiled) -> i64 {
+    search_valid(program, code, &[1, 2, 3, 4, 5, 6, 7, 8, 9])
+}
+
+fn digits(mut number: i64) -> Vec<i64> {
+    let mut digits = Vec::new();
+    while number != 0 {
+        digits.push(number % 10);
+        number /= 10;
     }
-    reversed
+    digits.reverse();
+    digits
+}
+
+fn is_valid(program: &Program, code: &Compiled, number: i64) -> bool {
+    let mut p = program.clone();
+    p.run(&digits(number));
+    debug_assert_eq!(
+        p.registers,
+        code.run(&digits(number)),
+        "Compiled program differs from the interpreter"
+    );
+    p.z() == 0
 }
 
 fn main() {
     let filename = std::env::args().nth(1).expect("No filename given");
     let input = parse_input(&filename).expect("Failed to parse input");
+    let code = Compiled::new(&input.ops).expect("Failed to compile input");
+
+    // The digit constraints give both numbers right away, searching is only needed for programs
+    // that do not follow the pattern
+    let (biggest_valid_input, smallest_valid_input) = match symbolic::digit_constraints(&input.ops) {
+        Ok(constraints) => {
+            let len = input.ops.iter().filter(|op| op.inst == Instruction::INP).count();
+            (
+                symbolic::extreme_number(&constraints, len, true),
+                symbolic::extreme_number(&constraints, len, false),
+            )
+        }
+        Err(err) => {
+            println!("Symbolic analysis failed, searching instead: {}", err);
+            (biggest_valid(&input, &code), smallest_valid(&input, &code))
+        }
+    };
+    assert!(is_valid(&input, &code, biggest_valid_input));
+    assert!(is_valid(&input, &code, smallest_valid_input));
 
-    let biggest_valid_input = biggest_valid(&input);
     println!("ONE: Biggest valid input = {}", biggest_valid_input);
-
-    let smallest_valid_input = smallest_valid(&input);
     println!("TWO: Smallest valid input = {}", smallest_valid_input);
 }
@@ -0,0 +1,444 @@
+use std::fmt::Display;
+
+use crate::{Instruction, Op};
+
+const Z: usize = 3;
+
+/// Expression over the digits of the model number and the registers at the start of a block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    Const(i64),
+    /// Digit of the model number, counted from the left
+    Digit(usize),
+    /// Value of a register before the block, by register index
+    Start(usize),
+    Add(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Div(Box<Expr>, Box<Expr>),
+    Mod(Box<Expr>, Box<Expr>),
+    Eql(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Smallest and biggest value the expression can take. Digits are 1 to 9 and `z` is never
+    /// negative, other registers can hold anything.
+    fn range(&self) -> (i64, i64) {
+        match self {
+            Expr::Const(c) => (*c, *c),
+            Expr::Digit(_) => (1, 9),
+            Expr::Start(Z) => (0, i64::MAX),
+            Expr::Start(_) => (i64::MIN, i64::MAX),
+            Expr::Add(a, b) => {
+                let ((a_lo, a_hi), (b_lo, b_hi)) = (a.range(), b.range());
+                (a_lo.saturating_add(b_lo), a_hi.saturating_add(b_hi))
+            }
+            Expr::Mul(a, b) => {
+                let ((a_lo, a_hi), (b_lo, b_hi)) = (a.range(), b.range());
+                let products = [
+                    a_lo.saturating_mul(b_lo),
+                    a_lo.saturating_mul(b_hi),
+                    a_hi.saturating_mul(b_lo),
+                    a_hi.saturating_mul(b_hi),
+                ];
+                (
+                    *products.iter().min().unwrap(),
+                    *products.iter().max().unwrap(),
+                )
+            }
+            Expr::Div(a, b) => match (a.range(), b.as_ref()) {
+                ((lo, hi), Expr::Const(d)) if *d > 0 => (lo / d, hi / d),
+                _ => (i64::MIN, i64::MAX),
+            },
+            Expr::Mod(a, b) => match (a.range(), b.as_ref()) {
+                ((lo, hi), Expr::Const(m)) if lo >= 0 && *m > 0 => (0, hi.min(m - 1)),
+                _ => (i64::MIN, i64::MAX),
+            },
+            Expr::Eql(_, _) => (0, 1),
+        }
+    }
+
+    fn add(a: Expr, b: Expr) -> Expr {
+        match (a, b) {
+            (Expr::Const(a), Expr::Const(b)) => Expr::Const(a + b),
+            (Expr::Const(0), e) | (e, Expr::Const(0)) => e,
+            // Constants go to the right and are collected there
+            (Expr::Const(c), e) | (e, Expr::Const(c)) => match e {
+                Expr::Add(e, inner) if matches!(*inner, Expr::Const(_)) => {
+                    Expr::add(*e, Expr::add(*inner, Expr::Const(c)))
+                }
+                e => Expr::Add(Box::new(e), Box::new(Expr::Const(c))),
+            },
+            (a, b) => Expr::Add(Box::new(a), Box::new(b)),
+        }
+    }
+
+    fn mul(a: Expr, b: Expr) -> Expr {
+        match (a, b) {
+            (Expr::Const(a), Expr::Const(b)) => Expr::Const(a * b),
+            (Expr::Const(0), _) | (_, Expr::Const(0)) => Expr::Const(0),
+            (Expr::Const(1), e) | (e, Expr::Const(1)) => e,
+            (Expr::Const(c), e) | (e, Expr::Const(c)) => {
+                Expr::Mul(Box::new(e), Box::new(Expr::Const(c)))
+            }
+            (a, b) => Expr::Mul(Box::new(a), Box::new(b)),
+        }
+    }
+
+    fn div(a: Expr, b: Expr) -> Expr {
+        match (a, b) {
+            (Expr::Const(a), Expr::Const(b)) if b != 0 => Expr::Const(a / b),
+            (e, Expr::Const(1)) => e,
+            (e, Expr::Const(d)) if d > 0 && e.range().0 >= 0 && e.range().1 < d => Expr::Const(0),
+            (a, b) => Expr::Div(Box::new(a), Box::new(b)),
+        }
+    }
+
+    fn modulo(a: Expr, b: Expr) -> Expr {
+        match (a, b) {
+            (Expr::Const(a), Expr::Const(b)) if b > 0 => Expr::Const(a % b),
+            (e, Expr::Const(m)) if m > 0 && e.range().0 >= 0 && e.range().1 < m => e,
+            (a, b) => Expr::Mod(Box::new(a), Box::new(b)),
+        }
+    }
+
+    fn eql(a: Expr, b: Expr) -> Expr {
+        let ((a_lo, a_hi), (b_lo, b_hi)) = (a.range(), b.range());
+        if a_hi < b_lo || b_hi < a_lo {
+            return Expr::Const(0);
+        }
+        match (a, b) {
+            (Expr::Const(a), Expr::Const(b)) => Expr::Const((a == b) as i64),
+            (a, b) if a == b => Expr::Const(1),
+            (a, b) => Expr::Eql(Box::new(a), Box::new(b)),
+        }
+    }
+
+    /// Rebuilds the expression with every occurrence of `node` replaced by `value`.
+    fn substitute(&self, node: &Expr, value: &Expr) -> Expr {
+        if self == node {
+            return value.clone();
+        }
+        let sub = |e: &Expr| e.substitute(node, value);
+        match self {
+            Expr::Add(a, b) => Expr::add(sub(a), sub(b)),
+            Expr::Mul(a, b) => Expr::mul(sub(a), sub(b)),
+            Expr::Div(a, b) => Expr::div(sub(a), sub(b)),
+            Expr::Mod(a, b) => Expr::modulo(sub(a), sub(b)),
+            Expr::Eql(a, b) => Expr::eql(sub(a), sub(b)),
+            e => e.clone(),
+        }
+    }
+
+    /// First node, in pre-order, for which `pred` holds.
+    fn find(&self, pred: &impl Fn(&Expr) -> bool) -> Option<&Expr> {
+        if pred(self) {
+            return Some(self);
+        }
+        match self {
+            Expr::Add(a, b)
+            | Expr::Mul(a, b)
+            | Expr::Div(a, b)
+            | Expr::Mod(a, b)
+            | Expr::Eql(a, b) => a.find(pred).or_else(|| b.find(pred)),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::Const(c) => write!(f, "{}", c),
+            Expr::Digit(i) => write!(f, "w{}", i),
+            Expr::Start(r) => write!(f, "{}", ["w", "x", "y", "z"][*r]),
+            Expr::Add(a, b) => write!(f, "({} + {})", a, b),
+            Expr::Mul(a, b) => write!(f, "{} * {}", a, b),
+            Expr::Div(a, b) => write!(f, "{} / {}", a, b),
+            Expr::Mod(a, b) => write!(f, "{} % {}", a, b),
+            Expr::Eql(a, b) => write!(f, "[{} == {}]", a, b),
+        }
+    }
+}
+
+/// Runs the ops symbolically, `inp` reads the digit `digit`.
+pub fn execute(ops: &[Op], digit: usize) -> Result<[Expr; 4], String> {
+    let mut regs = [0, 1, 2, 3].map(Expr::Start);
+    for op in ops {
+        let a = op.op_a.to_index();
+        let b = match (&op.op_b, op.op_b_num) {
+            (Some(reg), _) => regs[reg.to_index()].clone(),
+            (None, Some(num)) => Expr::Const(num),
+            (None, None) => Expr::Const(0),
+        };
+        let a_val = regs[a].clone();
+        regs[a] = match op.inst {
+            Instruction::INP => Expr::Digit(digit),
+            Instruction::ADD => Expr::add(a_val, b),
+            Instruction::MUL => Expr::mul(a_val, b),
+            Instruction::DIV => Expr::div(a_val, b),
+            Instruction::MOD => Expr::modulo(a_val, b),
+            Instruction::EQL => Expr::eql(a_val, b),
+            Instruction::NoOp => return Err("Program contains an unknown instruction".to_string()),
+        };
+    }
+    Ok(regs)
+}
+
+/// What a digit block does with `z`, which is used as a stack of numbers in base `base`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Block {
+    /// Always pushes the digit plus `offset`
+    Push { base: i64, offset: i64 },
+    /// Pops the top number, and only if it plus `offset` is not the digit pushes the digit plus
+    /// something else
+    Pop { base: i64, offset: i64 },
+}
+
+/// `(base, offset)` if `z` is `z * base + digit + offset` with the pushed number being a single
+/// digit in that base.
+fn push_shape(z: &Expr, digit: usize) -> Option<(i64, i64)> {
+    let Expr::Add(shifted, pushed) = z else {
+        return None;
+    };
+    let Expr::Mul(start, base) = shifted.as_ref() else {
+        return None;
+    };
+    let (Expr::Start(Z), Expr::Const(base)) = (start.as_ref(), base.as_ref()) else {
+        return None;
+    };
+    let offset = match pushed.as_ref() {
+        Expr::Digit(i) if *i == digit => 0,
+        Expr::Add(d, c) => match (d.as_ref(), c.as_ref()) {
+            (Expr::Digit(i), Expr::Const(c)) if *i == digit => *c,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let (lo, hi) = pushed.range();
+    (lo >= 0 && hi < *base).then_some((*base, offset))
+}
+
+/// `(base, offset)` if `e` is the condition `[(z % base + offset) == digit]`.
+fn pop_condition(e: &Expr, digit: usize) -> Option<(i64, i64)> {
+    let Expr::Eql(lhs, rhs) = e else {
+        return None;
+    };
+    let Expr::Add(top, offset) = lhs.as_ref() else {
+        return None;
+    };
+    let Expr::Mod(start, base) = top.as_ref() else {
+        return None;
+    };
+    match (rhs.as_ref(), start.as_ref(), base.as_ref(), offset.as_ref()) {
+        (Expr::Digit(i), Expr::Start(Z), Expr::Const(base), Expr::Const(offset)) if *i == digit => {
+            Some((*base, *offset))
+        }
+        _ => None,
+    }
+}
+
+impl Block {
+    /// Recognizes the block reading digit `digit` from its `z` after the block.
+    fn classify(z: &Expr, digit: usize) -> Result<Self, String> {
+        if z.find(&|e| matches!(e, Expr::Start(r) if *r != Z))
+            .is_some()
+        {
+            return Err(format!(
+                "Block of digit {} depends on registers from before it: z = {}",
+                digit, z
+            ));
+        }
+        if let Some((base, offset)) = push_shape(z, digit) {
+            return Ok(Block::Push { base, offset });
+        }
+
+        // If the top of the stack plus offset is the digit the block has to reduce to z / base
+        if let Some(cond) = z.find(&|e| pop_condition(e, digit).is_some()) {
+            let (base, offset) = pop_condition(cond, digit).unwrap();
+            let popped = Expr::div(Expr::Start(Z), Expr::Const(base));
+            if z.substitute(cond, &Expr::Const(1)) == popped {
+                return Ok(Block::Pop { base, offset });
+            }
+        }
+        Err(format!(
+            "Block of digit {} is no push or pop: z = {}",
+            digit, z
+        ))
+    }
+}
+
+/// The digit at `digit` has to be the one at `other` plus `diff`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Constraint {
+    pub digit: usize,
+    pub other: usize,
+    pub diff: i64,
+}
+
+/// Splits the program into one block per digit and recognizes what each does with `z`.
+pub fn blocks(ops: &[Op]) -> Result<Vec<Block>, String> {
+    let starts = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, op)| op.inst == Instruction::INP)
+        .map(|(idx, _)| idx)
+        .collect::<Vec<_>>();
+    if starts.first() != Some(&0) {
+        return Err("Program does not start with reading a digit".to_string());
+    }
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(digit, start)| {
+            let end = starts.get(digit + 1).copied().unwrap_or(ops.len());
+            let [_, _, _, z] = execute(&ops[*start..end], digit)?;
+            Block::classify(&z, digit)
+        })
+        .collect()
+}
+
+/// Derives the constraints on the digits for `z` to be 0 after the program.
+///
+/// Every push needs a matching pop that does not push again, which only happens if the pushed
+/// digit plus both offsets is the popped digit.
+pub fn digit_constraints(ops: &[Op]) -> Result<Vec<Constraint>, String> {
+    let mut stack = Vec::new();
+    let mut constraints = Vec::new();
+    for (digit, block) in blocks(ops)?.into_iter().enumerate() {
+        match block {
+            Block::Push { base, offset } => stack.push((digit, base, offset)),
+            Block::Pop { base, offset } => {
+                let (other, pushed_base, pushed_offset) = stack
+                    .pop()
+                    .ok_or_else(|| format!("Digit {} pops from an empty stack", digit))?;
+                if base != pushed_base {
+                    return Err(format!(
+                        "Digit {} pops in base {} what digit {} pushed in base {}",
+                        digit, base, other, pushed_base
+                    ));
+                }
+                let diff = pushed_offset + offset;
+                if diff.abs() > 8 {
+                    return Err(format!(
+                        "Digits {} and {} would have to differ by {}",
+                        digit, other, diff
+                    ));
+                }
+                constraints.push(Constraint { digit, other, diff });
+            }
+        }
+    }
+    if !stack.is_empty() {
+        return Err(format!("{} pushes are never popped", stack.len()));
+    }
+    Ok(constraints)
+}
+
+/// Biggest or smallest number of `len` digits satisfying the constraints.
+pub fn extreme_number(constraints: &[Constraint], len: usize, biggest: bool) -> i64 {
+    let mut digits = vec![if biggest { 9 } else { 1 }; len];
+    for c in constraints {
+        // digit = other + diff, so pick the one that is limited by the other
+        let (digit, other) = match (biggest, c.diff >= 0) {
+            (true, true) => (9, 9 - c.diff),
+            (true, false) => (9 + c.diff, 9),
+            (false, true) => (1 + c.diff, 1),
+            (false, false) => (1, 1 - c.diff),
+        };
+        digits[c.digit] = digit;
+        digits[c.other] = other;
+    }
+    digits.iter().fold(0, |num, d| num * 10 + d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ops(source: &str) -> Vec<Op> {
+        source.lines().map(Op::from).collect()
+    }
+
+    /// One MONAD block in the shape of the puzzle input
+    fn block(div: i64, check: i64, offset: i64) -> String {
+        format!(
+            "inp w\nmul x 0\nadd x z\nmod x 26\ndiv z {}\nadd x {}\neql x w\neql x 0\nmul y 0\n\
+             add y 25\nmul y x\nadd y 1\nmul z y\nmul y 0\nadd y w\nadd y {}\nmul y x\nadd z y\n",
+            div, check, offset
+        )
+    }
+
+    #[test]
+    fn simplify_block() {
+        let [w, x, y, z] = execute(&ops(&block(1, 12, 6)), 0).unwrap();
+        assert_eq!(w, Expr::Digit(0));
+        assert_eq!(x, Expr::Const(1));
+        assert_eq!(y, Expr::add(Expr::Digit(0), Expr::Const(6)));
+        assert_eq!(z.to_string(), "(z * 26 + (w0 + 6))");
+
+        let [_, _, _, z] = execute(&ops(&block(26, -6, 8)), 3).unwrap();
+        assert_eq!(
+            z.to_string(),
+            "(z / 26 * ([[(z % 26 + -6) == w3] == 0] * 25 + 1) + (w3 + 8) * [[(z % 26 + -6) == w3] == 0])"
+        );
+    }
+
+    #[test]
+    fn constraints() {
+        let source = [
+            block(1, 12, 6),
+            block(1, 10, 2),
+            block(26, -6, 8),
+            block(26, -1, 3),
+        ]
+        .concat();
+        let program = ops(&source);
+        assert_eq!(
+            blocks(&program).unwrap(),
+            vec![
+                Block::Push {
+                    base: 26,
+                    offset: 6
+                },
+                Block::Push {
+                    base: 26,
+                    offset: 2
+                },
+                Block::Pop {
+                    base: 26,
+                    offset: -6
+                },
+                Block::Pop {
+                    base: 26,
+                    offset: -1
+                },
+            ]
+        );
+
+        let constraints = digit_constraints(&program).unwrap();
+        assert_eq!(
+            constraints,
+            vec![
+                Constraint {
+                    digit: 2,
+                    other: 1,
+                    diff: -4
+                },
+                Constraint {
+                    digit: 3,
+                    other: 0,
+                    diff: 5
+                },
+            ]
+        );
+        assert_eq!(extreme_number(&constraints, 4, true), 4959);
+        assert_eq!(extreme_number(&constraints, 4, false), 1516);
+
+        let unbalanced = [block(1, 12, 6), block(1, 10, 2), block(26, -6, 8)].concat();
+        assert_eq!(
+            digit_constraints(&ops(&unbalanced)),
+            Err("1 pushes are never popped".to_string())
+        );
+    }
+}
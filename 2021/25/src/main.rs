@@ -1,13 +1,15 @@
+use aoc_common::cycle;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Hash)]
 enum Direction {
     Right,
     Down,
     No,
 }
 
+#[derive(Clone, PartialEq, Hash)]
 struct Field {
     data: Vec<Vec<Direction>>,
 }
@@ -96,12 +98,14 @@ fn parse_input(filename: &str) -> Result<Field, std::io::Error> {
 
 fn main() {
     let filename = std::env::args().nth(1).expect("No filename given");
-    let mut input = parse_input(&filename).expect("Failed to parse input");
+    let input = parse_input(&filename).expect("Failed to parse input");
 
-    let mut steps = 1;
-    while input.step() > 0 {
-        steps += 1;
-    }
+    // Once no cucumber moves anymore the field stays the same forever, a cycle of length one
+    let settled = cycle::brent_hashed(input, |field| {
+        let mut next = field.clone();
+        next.step();
+        next
+    });
 
-    println!("First step no move occurs: {}", steps);
+    println!("First step no move occurs: {}", settled.start + 1);
 }
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use aoc_common::union_find::UnionFind;
 use std::collections::HashMap;
 
 static DIRECTIONS: [fn(row: usize, col: usize) -> Option<(usize, usize)>; 4] = [
@@ -9,52 +10,37 @@ static DIRECTIONS: [fn(row: usize, col: usize) -> Option<(usize, usize)>; 4] = [
 ];
 
 fn parse_input(input: &str) -> HashMap<char, Vec<Vec<(usize, usize)>>> {
-    let mut areas = HashMap::new();
-
-    for (row, line) in input.lines().enumerate() {
-        for (col, cell) in line.chars().enumerate() {
-            areas
-                .entry(cell)
-                .and_modify(|a| {
-                    insert_and_merge(a, (row, col));
-                })
-                .or_insert(vec![vec![(row, col)]]);
-        }
-    }
-
-    areas
-}
-
-fn insert_and_merge(areas: &mut Vec<Vec<(usize, usize)>>, (row, col): (usize, usize)) {
-    let mut inserted_areas = Vec::new();
-    for (i, area) in areas.iter().enumerate() {
-        'positions: for pos in area.iter() {
-            for dir in DIRECTIONS {
-                if let Some((next_row, next_col)) = dir(row, col) {
-                    if pos.0 == next_row && pos.1 == next_col {
-                        inserted_areas.push(i);
-                        break 'positions;
-                    }
+    let grid = input
+        .lines()
+        .map(|line| line.chars().collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let cols = grid.first().map(|row| row.len()).unwrap_or(0);
+
+    // Join every cell with its right and lower neighbour if they grow the same plant
+    let mut plots = UnionFind::new(grid.len() * cols);
+    for (row, line) in grid.iter().enumerate() {
+        for (col, cell) in line.iter().enumerate() {
+            for (next_row, next_col) in DIRECTIONS[..2].iter().filter_map(|dir| dir(row, col)) {
+                if grid.get(next_row).and_then(|l| l.get(next_col)) == Some(cell) {
+                    plots.union(row * cols + col, next_row * cols + next_col);
                 }
             }
         }
     }
 
-    if inserted_areas.len() == 0 {
-        // Push new area
-        areas.push(vec![(row, col)]);
-    } else if inserted_areas.len() == 1 {
-        let target_idx = inserted_areas.first().unwrap();
-        areas[*target_idx].push((row, col));
-    } else {
-        // Merge areas
-        let target_idx = inserted_areas.remove(0);
-        areas[target_idx].push((row, col));
-        for idx in inserted_areas.iter().rev() {
-            let mut tmp = areas.remove(*idx);
-            areas[target_idx].append(&mut tmp);
-        }
+    let mut areas = HashMap::new();
+    for area in plots.components() {
+        let area = area
+            .into_iter()
+            .map(|idx| (idx / cols, idx % cols))
+            .collect::<Vec<_>>();
+        areas
+            .entry(grid[area[0].0][area[0].1])
+            .or_insert_with(Vec::new)
+            .push(area);
     }
+
+    areas
 }
 
 fn fencing_price(area: &Vec<(usize, usize)>) -> usize {
@@ -1,4 +1,6 @@
 use anyhow::{bail, Context, Result};
+use aoc_common::linear_algebra::{Matrix, Rational};
+use aoc_common::number_theory::extended_gcd;
 use regex::Regex;
 
 #[derive(Debug)]
@@ -18,21 +20,65 @@ impl Machine {
     /// The costs for pressing button A are 3, the costs for pressing button B are 1.
     /// So total costs is 3 * a + b (result of this function)
     fn solve(&self, modifier: isize) -> isize {
-        let price = (self.price.0 + modifier, self.price.1 + modifier);
-
-        let pybx_pxby = price.1 * self.b.0 - price.0 * self.b.1;
-        let bxay_axby = self.b.0 * self.a.1 - self.a.0 * self.b.1;
-        let presses_a = pybx_pxby / bxay_axby;
+        let equations = Matrix::from_rows([[self.a.0, self.b.0], [self.a.1, self.b.1]]);
+        let price = [self.price.0 + modifier, self.price.1 + modifier].map(Rational::from);
+
+        let Some(solution) = equations.solve(&price) else {
+            return 0;
+        };
+        if !solution.is_unique() {
+            // Both buttons move the claw along the same line, so only one equation is left
+            let (a, b, price) = if self.a.0 != 0 || self.b.0 != 0 {
+                (self.a.0, self.b.0, price[0])
+            } else {
+                (self.a.1, self.b.1, price[1])
+            };
+            let price = price.to_integer().expect("Price is an integer");
+            return solve_collinear(a as i128, b as i128, price).unwrap_or(0) as isize;
+        }
 
-        if pybx_pxby % bxay_axby == 0 {
-            let pressed_b = (price.1 - presses_a * self.a.1) / self.b.1;
-            (3 * presses_a + pressed_b) as isize
-        } else {
-            0
+        // Buttons can only be pressed a whole and non-negative number of times
+        match solution.particular[..] {
+            [a, b] => match (a.to_integer(), b.to_integer()) {
+                (Some(a), Some(b)) if a >= 0 && b >= 0 => (3 * a + b) as isize,
+                _ => 0,
+            },
+            _ => unreachable!("System has exactly two variables"),
         }
     }
 }
 
+/// Cheapest non-negative solution of `a * presses_a + b * presses_b = price` with the same costs
+/// as [`Machine::solve`].
+fn solve_collinear(a: i128, b: i128, price: i128) -> Option<i128> {
+    // A button that does not move the claw at all is never worth pressing
+    match (a, b) {
+        (0, 0) => return (price == 0).then_some(0),
+        (0, b) => return (price % b == 0 && price / b >= 0).then_some(price / b),
+        (a, 0) => return (price % a == 0 && price / a >= 0).then_some(3 * (price / a)),
+        _ => (),
+    }
+
+    let (g, x, y) = extended_gcd(a, b);
+    if price % g != 0 {
+        return None;
+    }
+
+    // All integer solutions are (x0 + k * step_a, y0 - k * step_b), both presses have to stay
+    // non-negative which bounds k from both sides
+    let (x0, y0) = (x * (price / g), y * (price / g));
+    let (step_a, step_b) = (b / g, a / g);
+    let k_min = -(x0.div_euclid(step_a));
+    let k_max = y0.div_euclid(step_b);
+    if k_min > k_max {
+        return None;
+    }
+
+    // The costs change linearly with k, so the cheapest solution is at one of the bounds
+    let costs = |k: i128| 3 * (x0 + k * step_a) + (y0 - k * step_b);
+    Some(costs(k_min).min(costs(k_max)))
+}
+
 fn parse_input(input: &str) -> Result<Vec<Machine>> {
     let button_matcher = Regex::new("Button \\w: X\\+(?<x>\\d+), Y\\+(?<y>\\d+)")?;
     let price_matcher = Regex::new("Prize: X=(?<x>\\d+), Y=(?<y>\\d+)")?;
@@ -125,4 +171,24 @@ Prize: X=18641, Y=10279";
             .sum::<isize>();
         // No value given by AoC, so just run it and check if we do not get panics
     }
+
+    #[test]
+    fn collinear_buttons() {
+        let machines = parse_input(
+            "Button A: X+2, Y+4
+Button B: X+1, Y+2
+Prize: X=10, Y=20
+
+Button A: X+4, Y+4
+Button B: X+1, Y+1
+Prize: X=8, Y=8
+
+Button A: X+4, Y+6
+Button B: X+2, Y+3
+Prize: X=7, Y=9",
+        )
+        .unwrap();
+        let costs = machines.iter().map(|m| m.solve(0)).collect::<Vec<_>>();
+        assert_eq!(costs, vec![10, 6, 0]);
+    }
 }
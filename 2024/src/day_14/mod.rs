@@ -1,4 +1,5 @@
 use anyhow::{Context, Result};
+use aoc_common::number_theory;
 use regex::Regex;
 
 #[derive(Clone, Debug, Default)]
@@ -53,43 +54,23 @@ fn calc_safety_factor(robots: &[Robot], bounds: &(isize, isize)) -> usize {
     quadrants.iter().fold(1, |acc, q| acc * q)
 }
 
-fn calc_extended_safety_factor(robots: &[Robot], bounds: &(isize, isize)) -> usize {
-    let first_x = bounds.0 / 3;
-    let sec_x = (bounds.0 / 3) * 2 + 1;
-    let first_y = bounds.1 / 3;
-    let sec_y = (bounds.1 / 3) * 2 + 1;
-
-    let mut quadrants = [0; 9];
-
-    for r in robots {
-        if r.pos.0 < first_x {
-            if r.pos.1 < first_y {
-                quadrants[0] += 1;
-            } else if r.pos.1 > first_y && r.pos.1 < sec_y {
-                quadrants[1] += 1;
-            } else if r.pos.1 > sec_y {
-                quadrants[2] += 1;
-            }
-        } else if r.pos.0 < sec_x && r.pos.0 > first_x {
-            if r.pos.1 < first_y {
-                quadrants[3] += 1;
-            } else if r.pos.1 > first_y && r.pos.1 < sec_y {
-                quadrants[4] += 1;
-            } else if r.pos.1 > sec_y {
-                quadrants[5] += 1;
-            }
-        } else if r.pos.0 > sec_x {
-            if r.pos.1 < first_y {
-                quadrants[6] += 1;
-            } else if r.pos.1 > first_y && r.pos.1 < sec_y {
-                quadrants[7] += 1;
-            } else if r.pos.1 > sec_y {
-                quadrants[8] += 1;
-            }
-        }
-    }
-
-    quadrants.iter().fold(1, |acc, q| acc * q)
+/// Step in `0..period` at which the robots are packed the tightest along one axis, which is
+/// where their positions have the lowest variance
+fn min_variance_step(
+    robots: &[Robot],
+    period: isize,
+    axis: impl Fn(&(isize, isize)) -> isize,
+) -> isize {
+    (0..period)
+        .min_by_key(|step| {
+            let positions = robots
+                .iter()
+                .map(|r| (axis(&r.pos) + step * axis(&r.vel)).rem_euclid(period))
+                .collect::<Vec<_>>();
+            let mean = positions.iter().sum::<isize>() / positions.len() as isize;
+            positions.iter().map(|p| (p - mean).pow(2)).sum::<isize>()
+        })
+        .unwrap_or(0)
 }
 
 pub fn run() -> Result<()> {
@@ -98,6 +79,7 @@ pub fn run() -> Result<()> {
         env!("CARGO_MANIFEST_DIR")
     ))?;
     let mut robots = parse_input(&input)?;
+    let initial = robots.clone();
     let bounds = (101, 103);
 
     for r in &mut robots {
@@ -106,22 +88,14 @@ pub fn run() -> Result<()> {
     let safety_factor = calc_safety_factor(&robots, &bounds);
     println!("Day 14, Part 1: Safety factor after 100 steps: {safety_factor}");
 
-    // Christmas tree means most robots are cumulated at a single sector.
-    // Unfortunately the 4 quadrants from part one are not enought to find the tree for my input so
-    // I use 9 quadrants here instead. Then the tree should be formed when the safety factor is
-    // lowest
-    let mut min_safety_factor = usize::MAX;
-    let mut min_safety_factor_after = 100;
-    for i in 1..10_000 {
-        for r in &mut robots {
-            r.steps(1, &bounds);
-        }
-        let new_safety_factor = calc_extended_safety_factor(&robots, &bounds);
-        if min_safety_factor > new_safety_factor {
-            min_safety_factor = new_safety_factor;
-            min_safety_factor_after = i + 100;
-        }
-    }
+    // Christmas tree means most robots are cumulated in a small area. The x positions repeat every
+    // 101 steps and the y positions every 103 steps, so the tightest step of each axis is found
+    // independently and both are combined with the chinese remainder theorem
+    let tx = min_variance_step(&initial, bounds.0, |p| p.0);
+    let ty = min_variance_step(&initial, bounds.1, |p| p.1);
+    let (min_safety_factor_after, _) =
+        number_theory::crt(&[(tx as i128, bounds.0 as i128), (ty as i128, bounds.1 as i128)])
+            .context("Robot periods are incompatible")?;
     println!("Day 14, Part 2b: Christmas tree found after: {min_safety_factor_after}");
 
     Ok(())
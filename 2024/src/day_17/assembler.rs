@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Context, Result};
+
+/// Mnemonics in the order of their op codes
+const MNEMONICS: [&str; 8] = ["adv", "bxl", "bst", "jnz", "bxc", "out", "bdv", "cdv"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OperandKind {
+    /// `0` to `3` or one of the registers `a`, `b` and `c`
+    Combo,
+    /// `0` to `7`, for `jnz` also a label
+    Literal,
+    /// Ignored by the instruction, a literal defaulting to `0`
+    Ignored,
+}
+
+fn operand_kind(opcode: usize) -> OperandKind {
+    match opcode {
+        1 | 3 => OperandKind::Literal,
+        4 => OperandKind::Ignored,
+        _ => OperandKind::Combo,
+    }
+}
+
+/// One instruction of the source with the line it is on.
+struct SourceInstruction<'a> {
+    line: usize,
+    opcode: usize,
+    operand: Option<&'a str>,
+}
+
+/// Assembles mnemonic source into a program in the comma separated puzzle format.
+///
+/// Every line holds at most one instruction like `adv 3`, `out b` or `jnz loop`, optionally
+/// preceded by a `label:` and followed by a `;` comment. Labels can be jumped to as long as their
+/// address fits into the 3-bit operand.
+pub(super) fn assemble(source: &str) -> Result<String> {
+    let mut labels = HashMap::new();
+    let mut instructions = Vec::new();
+
+    for (idx, line) in source.lines().enumerate() {
+        let line_nr = idx + 1;
+        let mut code = line.split(';').next().unwrap_or_default().trim();
+        if let Some((label, rest)) = code.split_once(':') {
+            let label = label.trim();
+            if !is_label(label) {
+                bail!("Line {line_nr}: Invalid label {label}");
+            }
+            let addr = 2 * instructions.len();
+            if labels.insert(label, addr).is_some() {
+                bail!("Line {line_nr}: Label {label} is defined twice");
+            }
+            code = rest.trim();
+        }
+
+        let mut words = code.split_whitespace();
+        let Some(mnemonic) = words.next() else {
+            continue;
+        };
+        let opcode = MNEMONICS
+            .iter()
+            .position(|m| mnemonic.eq_ignore_ascii_case(m))
+            .with_context(|| format!("Line {line_nr}: Unknown mnemonic {mnemonic}"))?;
+        let operand = words.next();
+        if words.next().is_some() {
+            bail!("Line {line_nr}: Too many operands for {mnemonic}");
+        }
+        instructions.push(SourceInstruction {
+            line: line_nr,
+            opcode,
+            operand,
+        });
+    }
+
+    let mut program = Vec::with_capacity(2 * instructions.len());
+    for inst in &instructions {
+        let operand =
+            encode_operand(inst, &labels).with_context(|| format!("Line {}", inst.line))?;
+        program.push(inst.opcode.to_string());
+        program.push(operand.to_string());
+    }
+    Ok(program.join(","))
+}
+
+fn is_label(label: &str) -> bool {
+    label.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn encode_operand(inst: &SourceInstruction, labels: &HashMap<&str, usize>) -> Result<usize> {
+    let mnemonic = MNEMONICS[inst.opcode];
+    let kind = operand_kind(inst.opcode);
+    let Some(operand) = inst.operand else {
+        if kind == OperandKind::Ignored {
+            return Ok(0);
+        }
+        bail!("Missing operand for {mnemonic}");
+    };
+
+    match (kind, operand) {
+        (OperandKind::Combo, "a" | "A") => return Ok(4),
+        (OperandKind::Combo, "b" | "B") => return Ok(5),
+        (OperandKind::Combo, "c" | "C") => return Ok(6),
+        _ => (),
+    }
+
+    if let Ok(value) = operand.parse::<usize>() {
+        return match (kind, value) {
+            (OperandKind::Combo, 0..=3) => Ok(value),
+            (OperandKind::Combo, 4..=6) => {
+                bail!("Combo operand {value} of {mnemonic} reads a register, write a, b or c")
+            }
+            (OperandKind::Combo, 7) => bail!("Combo operand 7 of {mnemonic} is reserved"),
+            (_, 0..=7) if kind != OperandKind::Combo => Ok(value),
+            _ => bail!("Operand {value} of {mnemonic} does not fit into 3 bits"),
+        };
+    }
+
+    if inst.opcode != 3 {
+        bail!("Invalid operand {operand} for {mnemonic}");
+    }
+    let addr = *labels
+        .get(operand)
+        .with_context(|| format!("Undefined label {operand}"))?;
+    if addr > 7 {
+        bail!("Label {operand} at {addr} is out of reach of the 3-bit operand of jnz");
+    }
+    Ok(addr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{parse_op_codes, ChronospatialComputer, Registers};
+    use super::*;
+
+    /// Assembles and runs `source` on the given registers, returns the output and the registers
+    /// at the end.
+    fn run(source: &str, a: isize, b: isize, c: isize) -> (String, Registers) {
+        let ops = parse_op_codes(&assemble(source).unwrap()).unwrap();
+        let mut computer = ChronospatialComputer {
+            reg_a: a,
+            reg_b: b,
+            reg_c: c,
+            inst_ptr: 0,
+        };
+        let out = computer.run(&ops).unwrap();
+        (out, computer.registers())
+    }
+
+    #[test]
+    fn assemble_puzzle_program() {
+        let source = "
+            ; Input of the puzzle, every loop outputs one value for the lowest 3 bits of a
+            loop:
+                bst a
+                bxl 1
+                cdv b   ; c = a >> b
+                bxl 5
+                bxc
+                out b
+                adv 3
+                jnz loop
+        ";
+        assert_eq!(assemble(source).unwrap(), "2,4,1,1,7,5,1,5,4,0,5,5,0,3,3,0");
+        assert_eq!(assemble("").unwrap(), "");
+    }
+
+    #[test]
+    fn corpus() {
+        // Examples of the puzzle description
+        assert_eq!(run("bst c", 0, 0, 9).1.b, 1);
+        assert_eq!(run("out 0\nout 1\nout a", 10, 0, 0).0, "0,1,2");
+        let (out, registers) = run("start: adv 1\nout a\njnz start", 2024, 0, 0);
+        assert_eq!(out, "4,2,5,6,7,7,7,7,3,1,0");
+        assert_eq!(registers.a, 0);
+        assert_eq!(run("bxl 7", 0, 29, 0).1.b, 26);
+        assert_eq!(run("bxc", 0, 2024, 43690).1.b, 44354);
+
+        // The second example outputs itself
+        assert_eq!(
+            run("adv 3\nout a\njnz 0", 117440, 0, 0).0,
+            assemble("adv 3\nout a\njnz 0").unwrap()
+        );
+
+        // Labels resolve to addresses, also forward ones
+        let source = "jnz skip\nout 1\nskip: out 2";
+        assert_eq!(assemble(source).unwrap(), "3,4,5,1,5,2");
+        assert_eq!(run(source, 1, 0, 0).0, "2");
+        assert_eq!(run(source, 0, 0, 0).0, "1,2");
+
+        // Halves a until it is zero with a loop that does not start at the beginning
+        let source = "
+                bdv 0       ; b = a >> 0 is a copy of a
+                bxl 3
+            halve: out a
+                adv 1
+                bst b
+                jnz halve
+        ";
+        assert_eq!(run(source, 6, 0, 0).0, "6,3,1");
+    }
+
+    #[test]
+    fn invalid_source() {
+        let err = |source: &str| format!("{:#}", assemble(source).unwrap_err());
+        assert_eq!(err("out 7"), "Line 1: Combo operand 7 of out is reserved");
+        assert_eq!(
+            err("adv 1\nbst 4"),
+            "Line 2: Combo operand 4 of bst reads a register, write a, b or c"
+        );
+        assert_eq!(
+            err("bxl 8"),
+            "Line 1: Operand 8 of bxl does not fit into 3 bits"
+        );
+        assert_eq!(err("bxl a"), "Line 1: Invalid operand a for bxl");
+        assert_eq!(err("mul 3"), "Line 1: Unknown mnemonic mul");
+        assert_eq!(err("out"), "Line 1: Missing operand for out");
+        assert_eq!(err("out a b"), "Line 1: Too many operands for out");
+        assert_eq!(err("jnz end"), "Line 1: Undefined label end");
+        assert_eq!(err("x:\nx: out 1"), "Line 2: Label x is defined twice");
+        assert_eq!(err("1x: out 1"), "Line 1: Invalid label 1x");
+        assert_eq!(
+            err("out 1\nout 1\nout 1\nout 1\nend: jnz end"),
+            "Line 5: Label end at 8 is out of reach of the 3-bit operand of jnz"
+        );
+    }
+}
@@ -0,0 +1,451 @@
+use std::collections::BTreeSet;
+use std::fmt::Display;
+use std::io::{BufRead, Write};
+
+use anyhow::{bail, Context, Result};
+
+use super::disasm::{self, Instruction};
+use super::{join_output, ChronospatialComputer, OpCode, Registers, Step};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Register {
+    A,
+    B,
+    C,
+}
+
+impl Register {
+    fn get(&self, registers: &Registers) -> isize {
+        match self {
+            Self::A => registers.a,
+            Self::B => registers.b,
+            Self::C => registers.c,
+        }
+    }
+}
+
+impl TryFrom<&str> for Register {
+    type Error = anyhow::Error;
+    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
+        match value {
+            "a" | "A" => Ok(Self::A),
+            "b" | "B" => Ok(Self::B),
+            "c" | "C" => Ok(Self::C),
+            _ => bail!("Invalid register {value}"),
+        }
+    }
+}
+
+impl Display for Register {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::A => write!(f, "a"),
+            Self::B => write!(f, "b"),
+            Self::C => write!(f, "c"),
+        }
+    }
+}
+
+/// Record of one executed instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) struct TraceEntry {
+    /// The instruction at the instruction pointer it was executed at
+    pub(super) inst: Instruction,
+    pub(super) before: Registers,
+    pub(super) after: Registers,
+    pub(super) out: Option<u8>,
+}
+
+impl Display for TraceEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let inst = self.inst.to_string();
+        write!(
+            f,
+            "{:>3}: {inst:<6} {} -> {}",
+            self.inst.addr, self.before, self.after
+        )?;
+        if let Some(out) = self.out {
+            write!(f, " out {out}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Why [`Debugger::resume`] stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum StopReason {
+    Halted,
+    /// The instruction pointer reached a breakpoint, the instruction there is not executed yet
+    Breakpoint(usize),
+    /// The program wrote this many values
+    OutputCount(usize),
+    Watch {
+        register: Register,
+        old: isize,
+        new: isize,
+    },
+}
+
+impl Display for StopReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Halted => write!(f, "Halted"),
+            Self::Breakpoint(ip) => write!(f, "Breakpoint at {ip}"),
+            Self::OutputCount(count) => write!(f, "Wrote {count} values"),
+            Self::Watch { register, old, new } => {
+                write!(f, "Register {register} changed from {old} to {new}")
+            }
+        }
+    }
+}
+
+/// Runs a program on a [`ChronospatialComputer`] step by step.
+pub(super) struct Debugger<'a> {
+    initial: ChronospatialComputer,
+    computer: ChronospatialComputer,
+    ops: &'a [OpCode],
+    out: Vec<u8>,
+    breakpoints: BTreeSet<usize>,
+    output_breakpoint: Option<usize>,
+    watches: Vec<Register>,
+    /// Executed instructions, only recorded while tracing
+    trace: Option<Vec<TraceEntry>>,
+}
+
+impl<'a> Debugger<'a> {
+    pub(super) fn new(computer: ChronospatialComputer, ops: &'a [OpCode]) -> Self {
+        Self {
+            initial: computer.clone(),
+            computer,
+            ops,
+            out: Vec::new(),
+            breakpoints: BTreeSet::new(),
+            output_breakpoint: None,
+            watches: Vec::new(),
+            trace: None,
+        }
+    }
+
+    /// Starts the program over, optionally with a different value in register a. Breakpoints,
+    /// watches and tracing are kept, the recorded trace is cleared.
+    pub(super) fn reset(&mut self, a: Option<isize>) {
+        if let Some(a) = a {
+            self.initial.reg_a = a;
+        }
+        self.computer = self.initial.clone();
+        self.out.clear();
+        if let Some(trace) = &mut self.trace {
+            trace.clear();
+        }
+    }
+
+    pub(super) fn registers(&self) -> Registers {
+        self.computer.registers()
+    }
+
+    pub(super) fn inst_ptr(&self) -> usize {
+        self.computer.inst_ptr
+    }
+
+    pub(super) fn output(&self) -> &[u8] {
+        &self.out
+    }
+
+    pub(super) fn add_breakpoint(&mut self, ip: usize) {
+        self.breakpoints.insert(ip);
+    }
+
+    pub(super) fn remove_breakpoint(&mut self, ip: usize) -> bool {
+        self.breakpoints.remove(&ip)
+    }
+
+    /// Stops once the output holds `count` values.
+    pub(super) fn break_on_output(&mut self, count: Option<usize>) {
+        self.output_breakpoint = count;
+    }
+
+    /// Stops whenever `register` changes.
+    pub(super) fn watch(&mut self, register: Register) {
+        if !self.watches.contains(&register) {
+            self.watches.push(register);
+        }
+    }
+
+    pub(super) fn unwatch(&mut self, register: Register) {
+        self.watches.retain(|watched| *watched != register);
+    }
+
+    pub(super) fn set_tracing(&mut self, enabled: bool) {
+        match (enabled, &self.trace) {
+            (true, None) => self.trace = Some(Vec::new()),
+            (false, Some(_)) => self.trace = None,
+            _ => (),
+        }
+    }
+
+    pub(super) fn trace(&self) -> &[TraceEntry] {
+        self.trace.as_deref().unwrap_or_default()
+    }
+
+    /// Executes a single instruction, `None` if the program already halted.
+    pub(super) fn step(&mut self) -> Result<Option<TraceEntry>> {
+        let ip = self.computer.inst_ptr;
+        let Some(op) = self.ops.get(ip) else {
+            return Ok(None);
+        };
+        let inst = Instruction::decode(ip, op)?;
+        let before = self.computer.registers();
+        let Step::Executed { out } = self.computer.step(self.ops)? else {
+            return Ok(None);
+        };
+
+        let entry = TraceEntry {
+            inst,
+            before,
+            after: self.computer.registers(),
+            out,
+        };
+        self.out.extend(out);
+        if let Some(trace) = &mut self.trace {
+            trace.push(entry);
+        }
+        Ok(Some(entry))
+    }
+
+    /// Runs until the program halts or a breakpoint or watch triggers. The instruction at the
+    /// instruction pointer is always executed, so resuming at a breakpoint moves on.
+    pub(super) fn resume(&mut self) -> Result<StopReason> {
+        let mut first = true;
+        loop {
+            let ip = self.computer.inst_ptr;
+            if !first && self.breakpoints.contains(&ip) {
+                return Ok(StopReason::Breakpoint(ip));
+            }
+            first = false;
+
+            let Some(entry) = self.step()? else {
+                return Ok(StopReason::Halted);
+            };
+            if entry.out.is_some() && self.output_breakpoint == Some(self.out.len()) {
+                return Ok(StopReason::OutputCount(self.out.len()));
+            }
+            for register in &self.watches {
+                let (old, new) = (register.get(&entry.before), register.get(&entry.after));
+                if old != new {
+                    return Ok(StopReason::Watch {
+                        register: *register,
+                        old,
+                        new,
+                    });
+                }
+            }
+        }
+    }
+}
+
+const HELP: &str = "Commands:
+  s, step [n]          execute n instructions (default 1)
+  c, continue          run until halted or stopped by a breakpoint or watch
+  b, break <ip>        stop before executing the instruction at ip
+  d, delete <ip>       remove the breakpoint at ip
+  o, output [n]        stop once n values were written, no n removes it
+  w, watch <reg>       stop when register a, b or c changes
+  u, unwatch <reg>     stop watching the register
+  t, trace [on|off]    show the recorded trace, or turn recording on or off
+  r, regs              show the registers, instruction pointer and output
+  l, list              show the disassembled program
+  reset [a]            start over, optionally with a new value for register a
+  h, help              show this help
+  q, quit              leave the debugger";
+
+/// Reads debugger commands line by line from `input` until `quit` or the end of the input.
+pub(super) fn repl(
+    debugger: &mut Debugger,
+    input: impl BufRead,
+    mut out: impl Write,
+) -> Result<()> {
+    write!(out, "({}) ", debugger.inst_ptr())?;
+    out.flush()?;
+    for line in input.lines() {
+        let line = line?;
+        let mut words = line.split_whitespace();
+        let Some(command) = words.next() else {
+            write!(out, "({}) ", debugger.inst_ptr())?;
+            out.flush()?;
+            continue;
+        };
+        if matches!(command, "q" | "quit") {
+            break;
+        }
+
+        if let Err(err) = execute(debugger, command, words.next(), &mut out) {
+            writeln!(out, "Error: {err}")?;
+        }
+        write!(out, "({}) ", debugger.inst_ptr())?;
+        out.flush()?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+fn execute(
+    debugger: &mut Debugger,
+    command: &str,
+    arg: Option<&str>,
+    out: &mut impl Write,
+) -> Result<()> {
+    let number = |arg: Option<&str>| -> Result<usize> {
+        arg.context("Missing argument")?
+            .parse::<usize>()
+            .context("Invalid number")
+    };
+    let register = |arg: Option<&str>| -> Result<Register> {
+        Register::try_from(arg.context("Missing register")?)
+    };
+
+    match command {
+        "s" | "step" => {
+            let count = arg.map_or(Ok(1), |_| number(arg))?;
+            for _ in 0..count {
+                match debugger.step()? {
+                    Some(entry) => writeln!(out, "{entry}")?,
+                    None => {
+                        writeln!(out, "{}", StopReason::Halted)?;
+                        break;
+                    }
+                }
+            }
+        }
+        "c" | "continue" => {
+            let reason = debugger.resume()?;
+            writeln!(out, "{reason}, {}", debugger.registers())?;
+        }
+        "b" | "break" => debugger.add_breakpoint(number(arg)?),
+        "d" | "delete" => {
+            let ip = number(arg)?;
+            if !debugger.remove_breakpoint(ip) {
+                bail!("No breakpoint at {ip}");
+            }
+        }
+        "o" | "output" => debugger.break_on_output(arg.map(|_| number(arg)).transpose()?),
+        "w" | "watch" => debugger.watch(register(arg)?),
+        "u" | "unwatch" => debugger.unwatch(register(arg)?),
+        "t" | "trace" => match arg {
+            Some("on") => debugger.set_tracing(true),
+            Some("off") => debugger.set_tracing(false),
+            Some(arg) => bail!("Expected on or off, got {arg}"),
+            None => {
+                for entry in debugger.trace() {
+                    writeln!(out, "{entry}")?;
+                }
+            }
+        },
+        "r" | "regs" => writeln!(
+            out,
+            "{} ip={} out={}",
+            debugger.registers(),
+            debugger.inst_ptr(),
+            join_output(debugger.output())
+        )?,
+        "l" | "list" => {
+            for line in disasm::listing(debugger.ops)?.lines() {
+                let current = line
+                    .trim_start()
+                    .starts_with(&format!("{}:", debugger.inst_ptr()));
+                writeln!(out, "{} {line}", if current { ">" } else { " " })?;
+            }
+        }
+        "reset" => {
+            let a = arg
+                .map(|a| a.parse::<isize>().context("Invalid value"))
+                .transpose()?;
+            debugger.reset(a);
+        }
+        "h" | "help" => writeln!(out, "{HELP}")?,
+        _ => bail!("Unknown command {command}, try help"),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parse_input;
+    use super::*;
+
+    const INPUT: &str = "Register A: 729
+Register B: 0
+Register C: 0
+
+Program: 0,1,5,4,3,0";
+
+    #[test]
+    fn step_and_trace() {
+        let (computer, ops) = parse_input(INPUT).unwrap();
+        let mut debugger = Debugger::new(computer, &ops);
+        debugger.set_tracing(true);
+
+        let entry = debugger.step().unwrap().unwrap();
+        assert_eq!(
+            entry.to_string(),
+            "  0: adv 1  a=729 b=0 c=0 -> a=364 b=0 c=0"
+        );
+        let entry = debugger.step().unwrap().unwrap();
+        assert_eq!(entry.out, Some(4));
+        assert_eq!(debugger.inst_ptr(), 4);
+
+        assert_eq!(debugger.resume().unwrap(), StopReason::Halted);
+        assert_eq!(join_output(debugger.output()), "4,6,3,5,6,3,5,2,1,0");
+        assert_eq!(debugger.trace().len(), 30);
+        assert!(debugger.step().unwrap().is_none());
+    }
+
+    #[test]
+    fn breakpoints_and_watches() {
+        let (computer, ops) = parse_input(INPUT).unwrap();
+        let mut debugger = Debugger::new(computer, &ops);
+
+        debugger.add_breakpoint(4);
+        assert_eq!(debugger.resume().unwrap(), StopReason::Breakpoint(4));
+        assert_eq!(debugger.resume().unwrap(), StopReason::Breakpoint(4));
+        assert_eq!(debugger.output(), [4, 6]);
+        assert!(debugger.remove_breakpoint(4));
+
+        debugger.break_on_output(Some(5));
+        assert_eq!(debugger.resume().unwrap(), StopReason::OutputCount(5));
+        debugger.break_on_output(None);
+
+        debugger.watch(Register::A);
+        assert_eq!(
+            debugger.resume().unwrap(),
+            StopReason::Watch {
+                register: Register::A,
+                old: 22,
+                new: 11
+            }
+        );
+
+        debugger.reset(Some(8));
+        debugger.unwatch(Register::A);
+        assert_eq!(debugger.resume().unwrap(), StopReason::Halted);
+        assert_eq!(debugger.output(), [4, 2, 1, 0]);
+    }
+
+    #[test]
+    fn repl_session() {
+        let (computer, ops) = parse_input(INPUT).unwrap();
+        let mut debugger = Debugger::new(computer, &ops);
+        let commands = "b 2\nc\nregs\nstep 2\nfoo\nq\nstep\n";
+        let mut out = Vec::new();
+        repl(&mut debugger, commands.as_bytes(), &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "(0) (0) Breakpoint at 2, a=364 b=0 c=0
+(2) a=364 b=0 c=0 ip=2 out=
+(2)   2: out a  a=364 b=0 c=0 -> a=364 b=0 c=0 out 4
+  4: jnz 0  a=364 b=0 c=0 -> a=364 b=0 c=0
+(0) Error: Unknown command foo, try help
+(0) \n"
+        );
+    }
+}
@@ -0,0 +1,236 @@
+use std::fmt::Display;
+
+use anyhow::{bail, Result};
+
+use super::OpCode;
+
+/// Operand of an instruction after resolving combo operands to the registers they read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Operand {
+    Literal(isize),
+    A,
+    B,
+    C,
+}
+
+impl Operand {
+    pub(super) fn combo(operand: isize) -> Result<Self> {
+        match operand {
+            0..=3 => Ok(Self::Literal(operand)),
+            4 => Ok(Self::A),
+            5 => Ok(Self::B),
+            6 => Ok(Self::C),
+            _ => bail!("Invalid combo operand {operand}"),
+        }
+    }
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Literal(value) => write!(f, "{value}"),
+            Self::A => write!(f, "a"),
+            Self::B => write!(f, "b"),
+            Self::C => write!(f, "c"),
+        }
+    }
+}
+
+/// One instruction of the program with its operand resolved.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) struct Instruction {
+    pub(super) addr: usize,
+    pub(super) opcode: usize,
+    /// `None` for `bxc`, which ignores its operand
+    pub(super) operand: Option<Operand>,
+    /// Operand as it is stored in the program
+    pub(super) raw: isize,
+}
+
+impl Instruction {
+    pub(super) fn decode(addr: usize, op: &OpCode) -> Result<Self> {
+        let (opcode, raw) = op.code();
+        let operand = match op {
+            OpCode::Bxl(_) | OpCode::Jnz(_) => Some(Operand::Literal(raw)),
+            OpCode::Bxc(_) => None,
+            _ => match Operand::combo(raw) {
+                Ok(operand) => Some(operand),
+                Err(err) => bail!("{err} at {addr}"),
+            },
+        };
+        Ok(Self {
+            addr,
+            opcode,
+            operand,
+            raw,
+        })
+    }
+
+    pub(super) fn mnemonic(&self) -> &'static str {
+        ["adv", "bxl", "bst", "jnz", "bxc", "out", "bdv", "cdv"][self.opcode]
+    }
+
+    /// Target of a `jnz`.
+    fn jump_target(&self) -> Option<usize> {
+        match (self.opcode, self.operand) {
+            (3, Some(Operand::Literal(target))) => target.try_into().ok(),
+            _ => None,
+        }
+    }
+
+    /// The instruction as a statement, jumps become `if a != 0 goto <addr>`.
+    fn statement(&self) -> String {
+        let operand = self.operand.unwrap_or(Operand::Literal(self.raw));
+        // Only the lowest three bits of a value are kept, which literal operands already are
+        let mod_8 = match operand {
+            Operand::Literal(_) => format!("{operand}"),
+            _ => format!("{operand} % 8"),
+        };
+        match self.opcode {
+            0 => format!("a >>= {operand}"),
+            1 => format!("b ^= {operand}"),
+            2 => format!("b = {mod_8}"),
+            3 => format!("if a != 0 goto {operand}"),
+            4 => "b ^= c".to_string(),
+            5 => format!("out({mod_8})"),
+            6 => format!("b = a >> {operand}"),
+            _ => format!("c = a >> {operand}"),
+        }
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.operand {
+            Some(operand) => write!(f, "{} {operand}", self.mnemonic()),
+            None => write!(f, "{}", self.mnemonic()),
+        }
+    }
+}
+
+/// Instructions of the program, they start at every even address.
+pub(super) fn disassemble(ops: &[OpCode]) -> Result<Vec<Instruction>> {
+    ops.iter()
+        .enumerate()
+        .step_by(2)
+        .map(|(addr, op)| Instruction::decode(addr, op))
+        .collect()
+}
+
+/// One line per instruction with its address, the raw program values and the mnemonic.
+pub(super) fn listing(ops: &[OpCode]) -> Result<String> {
+    let mut out = String::new();
+    for inst in disassemble(ops)? {
+        let raw = format!("{},{}", inst.opcode, inst.raw);
+        out.push_str(&format!("{:>3}: {raw:<5} {inst}\n", inst.addr));
+    }
+    Ok(out)
+}
+
+/// Address the loop starts at, if the only jump of the program is its last instruction and
+/// goes backwards.
+pub(super) fn loop_start(instructions: &[Instruction]) -> Option<usize> {
+    let jumps = instructions
+        .iter()
+        .filter(|inst| inst.opcode == 3)
+        .collect::<Vec<_>>();
+    match (jumps.as_slice(), instructions.last()) {
+        ([jump], Some(last)) if jump.addr == last.addr => jump
+            .jump_target()
+            .filter(|target| target % 2 == 0 && *target <= last.addr),
+        _ => None,
+    }
+}
+
+/// Pseudocode for the program.
+///
+/// Programs ending in the only jump, going backwards, become a `do { .. } while a != 0` loop with
+/// everything before its target in front of it. Any other control flow keeps the addresses and
+/// plain `goto`s.
+pub(super) fn decompile(ops: &[OpCode]) -> Result<String> {
+    let instructions = disassemble(ops)?;
+    let mut out = String::new();
+    let Some(loop_start) = loop_start(&instructions) else {
+        for inst in &instructions {
+            out.push_str(&format!("{:>3}: {};\n", inst.addr, inst.statement()));
+        }
+        return Ok(out);
+    };
+
+    let body = &instructions[..instructions.len() - 1];
+    for inst in body.iter().filter(|inst| inst.addr < loop_start) {
+        out.push_str(&format!("{};\n", inst.statement()));
+    }
+    out.push_str("do {\n");
+    for inst in body.iter().filter(|inst| inst.addr >= loop_start) {
+        out.push_str(&format!("    {};\n", inst.statement()));
+    }
+    out.push_str("} while a != 0\n");
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parse_op_codes;
+    use super::*;
+
+    #[test]
+    fn listing_resolves_combo_operands() {
+        let ops = parse_op_codes("2,4,1,1,7,5,4,1,5,5,0,3,3,0").unwrap();
+        assert_eq!(
+            listing(&ops).unwrap(),
+            "  0: 2,4   bst a
+  2: 1,1   bxl 1
+  4: 7,5   cdv b
+  6: 4,1   bxc
+  8: 5,5   out b
+ 10: 0,3   adv 3
+ 12: 3,0   jnz 0
+"
+        );
+    }
+
+    #[test]
+    fn decompile_loop() {
+        let ops = parse_op_codes("0,3,5,4,3,0").unwrap();
+        assert_eq!(
+            decompile(&ops).unwrap(),
+            "do {
+    a >>= 3;
+    out(a % 8);
+} while a != 0
+"
+        );
+
+        let ops = parse_op_codes("6,2,2,6,1,7,5,5,0,1,3,2").unwrap();
+        assert_eq!(
+            decompile(&ops).unwrap(),
+            "b = a >> 2;
+do {
+    b = c % 8;
+    b ^= 7;
+    out(b % 8);
+    a >>= 1;
+} while a != 0
+"
+        );
+    }
+
+    #[test]
+    fn decompile_without_loop() {
+        let ops = parse_op_codes("3,4,5,1,5,6").unwrap();
+        assert_eq!(
+            decompile(&ops).unwrap(),
+            "  0: if a != 0 goto 4;
+  2: out(1);
+  4: out(c % 8);
+"
+        );
+
+        let ops = parse_op_codes("5,7,3,0").unwrap();
+        assert_eq!(
+            disassemble(&ops).unwrap_err().to_string(),
+            "Invalid combo operand 7 at 0"
+        );
+    }
+}
@@ -3,6 +3,11 @@ use std::fmt::Display;
 use anyhow::{bail, Context, Result};
 use regex::Regex;
 
+mod assembler;
+mod debugger;
+mod disasm;
+mod quine;
+
 #[derive(Debug, PartialEq, Eq)]
 enum OpCode {
     Adv(isize), // 0
@@ -32,6 +37,22 @@ impl TryFrom<(usize, isize)> for OpCode {
     }
 }
 
+impl OpCode {
+    /// Op code and operand as they are stored in the program
+    fn code(&self) -> (usize, isize) {
+        match self {
+            Self::Adv(operand) => (0, *operand),
+            Self::Bxl(operand) => (1, *operand),
+            Self::Bst(operand) => (2, *operand),
+            Self::Jnz(operand) => (3, *operand),
+            Self::Bxc(operand) => (4, *operand),
+            Self::Out(operand) => (5, *operand),
+            Self::Bdv(operand) => (6, *operand),
+            Self::Cdv(operand) => (7, *operand),
+        }
+    }
+}
+
 impl Display for OpCode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let out = match self {
@@ -48,6 +69,28 @@ impl Display for OpCode {
     }
 }
 
+/// Snapshot of the registers of a [`ChronospatialComputer`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Registers {
+    a: isize,
+    b: isize,
+    c: isize,
+}
+
+impl Display for Registers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "a={} b={} c={}", self.a, self.b, self.c)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Step {
+    /// The instruction pointer is past the end of the program
+    Halted,
+    /// One instruction was executed, with the value it wrote to the output
+    Executed { out: Option<u8> },
+}
+
 #[derive(Clone, Debug)]
 struct ChronospatialComputer {
     reg_a: isize,
@@ -64,55 +107,64 @@ impl ChronospatialComputer {
         self.inst_ptr = 0;
     }
 
-    fn run(&mut self, ops: &[OpCode]) -> Result<String> {
-        let mut out = Vec::<u8>::new();
-        while let Some(op) = ops.get(self.inst_ptr) {
-            match op {
-                OpCode::Adv(operand) => {
-                    self.reg_a = self.reg_a >> self.decode_combo_operand(*operand)?;
-                }
-                OpCode::Bxl(operand) => self.reg_b ^= operand,
-                OpCode::Bst(operand) => {
-                    self.reg_b = ((self.decode_combo_operand(*operand)? % 8) + 8) % 8
-                }
-                OpCode::Jnz(operand) => {
-                    if self.reg_a != 0 {
-                        self.inst_ptr = self.decode_combo_operand(*operand)?.try_into()?;
-                        continue;
-                    }
-                }
-                OpCode::Bxc(_operand) => self.reg_b ^= self.reg_c,
-                OpCode::Out(operand) => {
-                    let val = ((self.decode_combo_operand(*operand)? % 8) + 8) % 8;
-                    println!("Out: {val}");
-                    out.push(val.try_into()?);
-                }
-                OpCode::Bdv(operand) => {
-                    self.reg_b = self.reg_a >> self.decode_combo_operand(*operand)?;
-                }
-                OpCode::Cdv(operand) => {
-                    self.reg_c = self.reg_a >> self.decode_combo_operand(*operand)?;
+    fn registers(&self) -> Registers {
+        Registers {
+            a: self.reg_a,
+            b: self.reg_b,
+            c: self.reg_c,
+        }
+    }
+
+    /// Executes the instruction at the instruction pointer.
+    fn step(&mut self, ops: &[OpCode]) -> Result<Step> {
+        let Some(op) = ops.get(self.inst_ptr) else {
+            return Ok(Step::Halted);
+        };
+        let mut out = None;
+        match op {
+            OpCode::Adv(operand) => {
+                self.reg_a = self.reg_a >> self.decode_combo_operand(*operand)?;
+            }
+            OpCode::Bxl(operand) => self.reg_b ^= operand,
+            OpCode::Bst(operand) => {
+                self.reg_b = ((self.decode_combo_operand(*operand)? % 8) + 8) % 8
+            }
+            OpCode::Jnz(operand) => {
+                if self.reg_a != 0 {
+                    self.inst_ptr = (*operand).try_into()?;
+                    return Ok(Step::Executed { out });
                 }
             }
-
-            self.inst_ptr += 2;
+            OpCode::Bxc(_operand) => self.reg_b ^= self.reg_c,
+            OpCode::Out(operand) => {
+                let val = ((self.decode_combo_operand(*operand)? % 8) + 8) % 8;
+                out = Some(val.try_into()?);
+            }
+            OpCode::Bdv(operand) => {
+                self.reg_b = self.reg_a >> self.decode_combo_operand(*operand)?;
+            }
+            OpCode::Cdv(operand) => {
+                self.reg_c = self.reg_a >> self.decode_combo_operand(*operand)?;
+            }
         }
 
-        // Output should be a string joined by ,
-        let out = out
-            .into_iter()
-            .enumerate()
-            .fold(String::new(), |mut s, (idx, it)| {
-                if idx == 0 {
-                    s.push_str(&format!("{it}"));
-                } else {
-                    s.push_str(&format!(",{it}"));
-                }
-                s
-            });
+        self.inst_ptr += 2;
+        Ok(Step::Executed { out })
+    }
+
+    /// Runs the program until it halts and returns the values it wrote.
+    fn run_output(&mut self, ops: &[OpCode]) -> Result<Vec<u8>> {
+        let mut out = Vec::<u8>::new();
+        while let Step::Executed { out: val } = self.step(ops)? {
+            out.extend(val);
+        }
         Ok(out)
     }
 
+    fn run(&mut self, ops: &[OpCode]) -> Result<String> {
+        Ok(join_output(&self.run_output(ops)?))
+    }
+
     fn decode_combo_operand(&self, operand: isize) -> Result<isize> {
         match operand {
             0..=3 => Ok(operand),
@@ -124,17 +176,12 @@ impl ChronospatialComputer {
     }
 }
 
-fn calc_a_reg_replicating(mut comp: ChronospatialComputer, ops: &[OpCode]) -> Result<isize> {
-    let (mut a, b, c) = (0, comp.reg_b, comp.reg_c);
-    for i in (0..ops.len()).rev() {
-        a <<= 3;
-        comp.reset(a, b, c);
-        while parse_op_codes(&comp.run(&ops)?)? != &ops[i..] {
-            a += 1;
-            comp.reset(a, b, c);
-        }
-    }
-    Ok(a)
+/// Output values as a string joined by ,
+fn join_output(out: &[u8]) -> String {
+    out.iter()
+        .map(|val| val.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
 }
 
 fn parse_op_codes(input: &str) -> Result<Vec<OpCode>> {
@@ -209,12 +256,66 @@ pub fn run() -> Result<()> {
     let output = computer.run(&ops)?;
     println!("Day 17, Part 1: Output of the program: {output}");
 
-    let a_val_replicating = calc_a_reg_replicating(computer_backup, &ops)?;
+    let a_val_replicating = quine::minimal_quine(&computer_backup, &ops)?
+        .context("No value for register a makes the program output itself")?;
     println!("Day 17, Part 2: Value for register a to creating self replicating output: {a_val_replicating}");
 
     Ok(())
 }
 
+/// Prints the disassembled program and pseudocode for it
+pub fn disasm() -> Result<()> {
+    let input = std::fs::read_to_string(format!(
+        "{}/src/day_17/input.txt",
+        env!("CARGO_MANIFEST_DIR")
+    ))?;
+    let (_, ops) = parse_input(&input)?;
+
+    println!("{}", disasm::listing(&ops)?);
+    print!("{}", disasm::decompile(&ops)?);
+
+    Ok(())
+}
+
+/// Prints every value of register a that makes the program output itself
+pub fn quines() -> Result<()> {
+    let input = std::fs::read_to_string(format!(
+        "{}/src/day_17/input.txt",
+        env!("CARGO_MANIFEST_DIR")
+    ))?;
+    let (computer, ops) = parse_input(&input)?;
+
+    let quines = quine::find_quines(&computer, &ops)?;
+    if quines.is_empty() {
+        println!("No value for register a makes the program output itself");
+    }
+    for a in quines {
+        println!("{a}");
+    }
+
+    Ok(())
+}
+
+/// Assembles the mnemonic source at `path` and prints the program
+pub fn asm(path: Option<String>) -> Result<()> {
+    let source = std::fs::read_to_string(path.context("No source file given")?)?;
+    println!("Program: {}", assembler::assemble(&source)?);
+
+    Ok(())
+}
+
+/// Interactive debugger for the program on stdin, see `help` for the commands
+pub fn debug() -> Result<()> {
+    let input = std::fs::read_to_string(format!(
+        "{}/src/day_17/input.txt",
+        env!("CARGO_MANIFEST_DIR")
+    ))?;
+    let (computer, ops) = parse_input(&input)?;
+
+    let mut debugger = debugger::Debugger::new(computer, &ops);
+    debugger::repl(&mut debugger, std::io::stdin().lock(), std::io::stdout())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -241,7 +342,20 @@ Program: 0,3,5,4,3,0";
     #[test]
     fn part_two() {
         let (computer, ops) = parse_input(INPUT_2).unwrap();
-        let a_val = calc_a_reg_replicating(computer, &ops).unwrap();
-        assert_eq!(a_val, 117440);
+        let a_val = quine::minimal_quine(&computer, &ops).unwrap();
+        assert_eq!(a_val, Some(117440));
+    }
+
+    #[test]
+    fn jump_operand_is_literal() {
+        // jnz 4 has to skip out 2 instead of jumping to the value of register a
+        let input = "Register A: 3\nRegister B: 0\nRegister C: 0\n\nProgram: 3,4,5,2,5,4";
+        let (mut computer, ops) = parse_input(input).unwrap();
+        assert_eq!(computer.run(&ops).unwrap(), "3");
+
+        // jnz 6 has to skip both outs instead of looping forever at register c
+        let input = "Register A: 5\nRegister B: 0\nRegister C: 0\n\nProgram: 3,6,5,2,5,3,5,4";
+        let (mut computer, ops) = parse_input(input).unwrap();
+        assert_eq!(computer.run(&ops).unwrap(), "5");
     }
 }
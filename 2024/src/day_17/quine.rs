@@ -0,0 +1,363 @@
+use anyhow::{bail, Result};
+
+use super::disasm::{self, Instruction, Operand};
+use super::{ChronospatialComputer, OpCode, Step};
+
+/// Register a of a quine has to fit into this many bits
+const MAX_BITS: u32 = 62;
+
+/// Shape of a program that works through register a a few bits per loop iteration.
+#[derive(Debug, PartialEq, Eq)]
+struct LoopShape {
+    /// Address of the first instruction of the loop
+    start: usize,
+    /// Bits a is shifted right by before the loop
+    preamble_shift: u32,
+    /// Bits a is shifted right by in every iteration
+    shift: u32,
+    /// Values written before the loop
+    preamble_outs: usize,
+    /// Values written in every iteration
+    outs: usize,
+    /// Number of iterations a quine has to run the loop for
+    iterations: usize,
+}
+
+/// Registers an instruction reads and the one it writes.
+fn registers_used(inst: &Instruction) -> (Vec<Operand>, Option<Operand>) {
+    let (mut reads, writes) = match inst.opcode {
+        0 => (vec![Operand::A], Some(Operand::A)),
+        1 => (vec![Operand::B], Some(Operand::B)),
+        2 => (vec![], Some(Operand::B)),
+        3 => (vec![Operand::A], None),
+        4 => (vec![Operand::B, Operand::C], Some(Operand::B)),
+        5 => (vec![], None),
+        6 => (vec![Operand::A], Some(Operand::B)),
+        _ => (vec![Operand::A], Some(Operand::C)),
+    };
+    reads.extend(inst.operand.filter(|op| !matches!(op, Operand::Literal(_))));
+    (reads, writes)
+}
+
+impl LoopShape {
+    /// Checks that a program of this shape can output itself and returns why not otherwise.
+    fn analyze(instructions: &[Instruction], len: usize) -> Result<Self> {
+        let outs = |insts: &[Instruction]| insts.iter().filter(|inst| inst.opcode == 5).count();
+        let Some(start) = disasm::loop_start(instructions) else {
+            if instructions.iter().any(|inst| inst.opcode == 3) {
+                bail!("Only programs ending in their single jump back to a loop can be searched");
+            }
+            bail!(
+                "Program without a loop writes {} of its {len} values at most",
+                outs(instructions)
+            );
+        };
+        let (preamble, body) = instructions.split_at(start / 2);
+        let body = &body[..body.len() - 1];
+
+        let shift = |insts: &[Instruction]| -> Result<u32> {
+            let mut bits = 0;
+            for inst in insts.iter().filter(|inst| inst.opcode == 0) {
+                match inst.operand {
+                    Some(Operand::Literal(value)) => bits += value as u32,
+                    Some(register) => bail!(
+                        "Register a is shifted by register {register} at {}, only constant shifts \
+                         can be searched",
+                        inst.addr
+                    ),
+                    None => unreachable!("adv has a combo operand"),
+                }
+            }
+            Ok(bits)
+        };
+        let (preamble_shift, shift) = (shift(preamble)?, shift(body)?);
+        let (preamble_outs, outs) = (outs(preamble), outs(body));
+
+        if shift == 0 {
+            bail!("Register a never changes in the loop, so it runs once or forever");
+        }
+        if outs == 0 || len <= preamble_outs || !(len - preamble_outs).is_multiple_of(outs) {
+            bail!(
+                "Loop writes {outs} values per iteration after {preamble_outs} before it, which \
+                 never adds up to the {len} values of the program"
+            );
+        }
+        let iterations = (len - preamble_outs) / outs;
+        let bits = preamble_shift + shift * iterations as u32;
+        if bits > MAX_BITS {
+            bail!("Register a would need {bits} bits, more than the supported {MAX_BITS}");
+        }
+
+        // Every iteration is searched on its own, which needs b and c to be set within it
+        let mut written = Vec::new();
+        for inst in body {
+            let (reads, writes) = registers_used(inst);
+            if let Some(register) = reads
+                .into_iter()
+                .find(|reg| *reg != Operand::A && !written.contains(reg))
+            {
+                bail!(
+                    "Register {register} is read at {} before it is set in the loop, so the \
+                     iterations depend on each other",
+                    inst.addr
+                );
+            }
+            written.extend(writes);
+        }
+
+        Ok(Self {
+            start,
+            preamble_shift,
+            shift,
+            preamble_outs,
+            outs,
+            iterations,
+        })
+    }
+}
+
+/// Values of the program, the operand of the last op code is its last value.
+fn program_values(ops: &[OpCode]) -> Vec<isize> {
+    ops.iter()
+        .map(|op| op.code().0 as isize)
+        .chain(ops.last().map(|op| op.code().1))
+        .collect()
+}
+
+struct Search<'a> {
+    computer: &'a ChronospatialComputer,
+    ops: &'a [OpCode],
+    program: Vec<u8>,
+    shape: LoopShape,
+    /// Stop once this many quines were found
+    limit: usize,
+}
+
+impl Search<'_> {
+    fn new<'a>(
+        computer: &'a ChronospatialComputer,
+        ops: &'a [OpCode],
+        limit: usize,
+    ) -> Result<Search<'a>> {
+        if ops.is_empty() {
+            bail!("Program is empty");
+        }
+        let program = program_values(ops);
+        if let Some(value) = program.iter().find(|value| !(0..8).contains(*value)) {
+            bail!("Program contains {value}, which can not be written as a 3-bit output");
+        }
+        let shape = LoopShape::analyze(&disasm::disassemble(ops)?, program.len())?;
+        Ok(Search {
+            computer,
+            ops,
+            program: program.iter().map(|value| *value as u8).collect(),
+            shape,
+            limit,
+        })
+    }
+
+    fn run(&self) -> Result<Vec<isize>> {
+        let mut found = Vec::new();
+        self.extend(self.shape.iterations - 1, 0, &mut found)?;
+        Ok(found)
+    }
+
+    /// Values written by one iteration of the loop starting with `a`.
+    fn iteration_output(&self, a: isize) -> Result<Vec<u8>> {
+        let mut comp = self.computer.clone();
+        comp.reg_a = a;
+        comp.inst_ptr = self.shape.start;
+
+        let mut out = Vec::new();
+        while let Step::Executed { out: val } = comp.step(self.ops)? {
+            out.extend(val);
+            if comp.inst_ptr == self.shape.start {
+                break;
+            }
+        }
+        Ok(out)
+    }
+
+    /// Collects the quines for which a at the start of iteration `iteration` of the loop is
+    /// shifted down to `next` by it. Candidates are tried in ascending order, so are the quines.
+    fn extend(&self, iteration: usize, next: isize, found: &mut Vec<isize>) -> Result<()> {
+        let first_out = self.shape.preamble_outs + iteration * self.shape.outs;
+        let expected = &self.program[first_out..first_out + self.shape.outs];
+
+        for chunk in 0..1 << self.shape.shift {
+            if found.len() >= self.limit {
+                break;
+            }
+            let a = next << self.shape.shift | chunk;
+            // An earlier iteration leaving 0 would have ended the loop
+            if a == 0 && iteration > 0 {
+                continue;
+            }
+            if self.iteration_output(a)? != expected {
+                continue;
+            }
+
+            if iteration > 0 {
+                self.extend(iteration - 1, a, found)?;
+                continue;
+            }
+            // Bits shifted out before the loop only matter to the preamble, verify every choice
+            // of them by running the whole program
+            for low in 0..1 << self.shape.preamble_shift {
+                let a = a << self.shape.preamble_shift | low;
+                let mut comp = self.computer.clone();
+                comp.reset(a, self.computer.reg_b, self.computer.reg_c);
+                if comp.run_output(self.ops)? == self.program && found.len() < self.limit {
+                    found.push(a);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// All values of register a for which the program outputs itself, in ascending order.
+///
+/// The loop shifts a right by a fixed number of bits each iteration, so the value of a at the
+/// start of an iteration is the one of the next iteration with that many new low bits. Going
+/// backwards from the last iteration, where a only has those low bits, every candidate is kept if
+/// its iteration writes the matching part of the program. Errors explain why a program can not be
+/// searched or never outputs itself.
+///
+/// Bits of a that no output depends on double the number of quines each, use [`minimal_quine`]
+/// if there can be many of them.
+pub(super) fn find_quines(computer: &ChronospatialComputer, ops: &[OpCode]) -> Result<Vec<isize>> {
+    Search::new(computer, ops, usize::MAX)?.run()
+}
+
+/// Lowest value of register a for which the program outputs itself, see [`find_quines`].
+pub(super) fn minimal_quine(
+    computer: &ChronospatialComputer,
+    ops: &[OpCode],
+) -> Result<Option<isize>> {
+    Ok(Search::new(computer, ops, 1)?.run()?.first().copied())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::assembler::assemble;
+    use super::super::parse_op_codes;
+    use super::*;
+
+    fn quines(source: &str) -> Result<Vec<isize>> {
+        let ops = parse_op_codes(&assemble(source).unwrap()).unwrap();
+        let computer = ChronospatialComputer {
+            reg_a: 0,
+            reg_b: 0,
+            reg_c: 0,
+            inst_ptr: 0,
+        };
+        find_quines(&computer, &ops)
+    }
+
+    /// Whether the program outputs itself when started with `a` in register a
+    fn outputs_itself(source: &str, a: isize) -> bool {
+        let program = assemble(source).unwrap();
+        let mut computer = ChronospatialComputer {
+            reg_a: a,
+            reg_b: 0,
+            reg_c: 0,
+            inst_ptr: 0,
+        };
+        computer.run(&parse_op_codes(&program).unwrap()).unwrap() == program
+    }
+
+    /// Values of a below `limit` for which the program outputs itself.
+    fn brute_force(source: &str, limit: isize) -> Vec<isize> {
+        (0..limit).filter(|a| outputs_itself(source, *a)).collect()
+    }
+
+    #[test]
+    fn puzzle_shape() {
+        // The low bits are shifted out before the first output, so they can be anything
+        let source = "adv 3\nout a\njnz 0";
+        assert_eq!(
+            quines(source).unwrap(),
+            (117440..117448).collect::<Vec<_>>()
+        );
+
+        let source = "bst a\nbxl 2\ncdv b\nbxl 3\nbxc\nout b\nadv 3\njnz 0";
+        let found = quines(source).unwrap();
+        assert_eq!(found.len(), 2);
+        assert!(found.iter().all(|a| outputs_itself(source, *a)));
+
+        let ops = parse_op_codes(&assemble(source).unwrap()).unwrap();
+        let computer = ChronospatialComputer {
+            reg_a: 0,
+            reg_b: 0,
+            reg_c: 0,
+            inst_ptr: 0,
+        };
+        assert_eq!(
+            minimal_quine(&computer, &ops).unwrap(),
+            found.first().copied()
+        );
+    }
+
+    #[test]
+    fn other_shifts() {
+        // Two values per iteration from six bits of a
+        let source = "bst a\nbxl 5\nout b\nbdv 3\nbxl 2\nout b\nadv 3\nadv 3\njnz 0";
+        let found = quines(source).unwrap();
+        assert_eq!(found.len(), 1);
+        assert!(outputs_itself(source, found[0]));
+
+        // The bit shifted out before the loop is never read
+        let source = "adv 1\nbst a\nbxl 5\nout b\nadv 3\njnz 2";
+        let found = quines(source).unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0] + 1, found[1]);
+        assert!(found.iter().all(|a| outputs_itself(source, *a)));
+
+        // Shifts below 3 bits read overlapping windows of a, small enough to check every value
+        for source in [
+            "bst a\nout b\nadv 2\njnz 0",
+            "bst a\nbxl 1\ncdv b\nbxc\nout b\nadv 1\njnz 0",
+        ] {
+            assert_eq!(
+                quines(source).unwrap(),
+                brute_force(source, 1 << 16),
+                "{source}"
+            );
+        }
+    }
+
+    #[test]
+    fn not_a_quine() {
+        let err = |source: &str| quines(source).unwrap_err().to_string();
+        assert_eq!(
+            err("out 0\nout 1"),
+            "Program without a loop writes 2 of its 4 values at most"
+        );
+        assert_eq!(
+            err("out a\njnz 0"),
+            "Register a never changes in the loop, so it runs once or forever"
+        );
+        assert_eq!(
+            err("adv 1\nout a\nout b\njnz 0"),
+            "Register b is read at 4 before it is set in the loop, so the iterations depend on \
+             each other"
+        );
+        assert_eq!(
+            err("adv b\nout a\njnz 0"),
+            "Register a is shifted by register b at 0, only constant shifts can be searched"
+        );
+        assert_eq!(
+            err("out a\nout a\nadv 3\nout a\njnz 0"),
+            "Loop writes 3 values per iteration after 0 before it, which never adds up to the 10 \
+             values of the program"
+        );
+        assert_eq!(
+            err("jnz 2\nout a\nadv 1\njnz 0"),
+            "Only programs ending in their single jump back to a loop can be searched"
+        );
+
+        // The shape fits, but no value of a works
+        assert_eq!(quines("adv 1\nout a\njnz 0").unwrap(), vec![]);
+    }
+}
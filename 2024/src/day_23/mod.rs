@@ -1,96 +1,67 @@
 use anyhow::{Context, Result};
+use aoc_common::graph::UndirectedGraph;
 use regex::Regex;
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap};
 
-fn parse_input(input: &str) -> Result<HashMap<String, BTreeSet<String>>> {
+#[derive(Debug)]
+struct Network {
+    names: Vec<String>,
+    graph: UndirectedGraph,
+}
+
+impl Network {
+    fn group(&self, nodes: &[usize]) -> BTreeSet<String> {
+        nodes.iter().map(|n| self.names[*n].clone()).collect()
+    }
+}
+
+fn parse_input(input: &str) -> Result<Network> {
+    let re = Regex::new("(?<a>[a-z]+)-(?<b>[a-z]+)")?;
     let parsed = input
         .lines()
         .map(|l| {
-            let re = Regex::new("(?<a>[a-z]+)-(?<b>[a-z]+)")?;
             let cap = re.captures(l).context("")?;
             let (_, [a, b]) = cap.extract();
-            Ok((a.into(), b.into()))
+            Ok((a, b))
         })
-        .collect::<Result<Vec<(String, String)>>>()?;
+        .collect::<Result<Vec<(&str, &str)>>>()?;
 
-    let mut connections = HashMap::<String, BTreeSet<String>>::new();
+    // Computers are numbered in alphabetical order
+    let names = parsed
+        .iter()
+        .flat_map(|(a, b)| [a.to_string(), b.to_string()])
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    let ids = names
+        .iter()
+        .enumerate()
+        .map(|(id, name)| (name.as_str(), id))
+        .collect::<HashMap<_, _>>();
 
-    for conn in parsed.into_iter() {
-        connections
-            .entry(conn.0.clone())
-            .or_default()
-            .insert(conn.1.clone());
-        connections.entry(conn.1).or_default().insert(conn.0);
+    let mut graph = UndirectedGraph::new(names.len());
+    for (a, b) in parsed {
+        graph.add_edge(ids[a], ids[b]);
     }
 
-    Ok(connections)
+    Ok(Network { names, graph })
 }
 
-fn group_connections(
-    connections: &HashMap<String, BTreeSet<String>>,
-) -> Result<HashSet<BTreeSet<String>>> {
-    let mut groups = HashSet::<BTreeSet<String>>::new();
-    for (start, s_reacheable) in connections.iter() {
-        for end in s_reacheable.iter().rev() {
-            for e_reacheable in connections.get(end).context("")?.iter().rev() {
-                if connections.get(e_reacheable).context("")?.contains(start) {
-                    groups.insert(BTreeSet::from([
-                        start.clone(),
-                        end.clone(),
-                        e_reacheable.clone(),
-                    ]));
-                }
-            }
-        }
-    }
-    Ok(groups)
+fn group_connections(network: &Network) -> Vec<BTreeSet<String>> {
+    network
+        .graph
+        .cliques_of_size(3)
+        .iter()
+        .map(|clique| network.group(clique))
+        .collect()
 }
 
-fn largest_group(connections: &HashMap<String, BTreeSet<String>>) -> Result<BTreeSet<String>> {
-    let mut groups = HashSet::<BTreeSet<String>>::new();
-    for (start, s_reacheable) in connections.iter() {
-        for end in s_reacheable.iter().rev() {
-            for e_reacheable in connections.get(end).context("")?.iter().rev() {
-                if connections.get(e_reacheable).context("")?.contains(start) {
-                    let mut matches = groups
-                        .iter()
-                        .filter(|g| {
-                            let all_reacheable = g
-                                .iter()
-                                .all(|gm| connections.get(gm).unwrap().contains(end));
-
-                            all_reacheable && g.contains(start) && g.contains(e_reacheable)
-                        })
-                        .cloned()
-                        .collect::<Vec<_>>();
-
-                    for mut g in matches.drain(..) {
-                        groups.remove(&g);
-                        g.insert(end.clone());
-                        groups.insert(g);
-                    }
-
-                    groups.insert(BTreeSet::from([
-                        start.clone(),
-                        end.clone(),
-                        e_reacheable.clone(),
-                    ]));
-                }
-            }
-        }
-    }
-
-    let mut max_len = 0;
-    let mut largest_set = None;
-
-    for g in groups.iter() {
-        if g.len() > max_len {
-            max_len = g.len();
-            largest_set = Some(g);
-        }
+fn largest_group(network: &Network) -> Result<BTreeSet<String>> {
+    let clique = network.graph.maximum_clique();
+    if clique.is_empty() {
+        anyhow::bail!("Network has no computers");
     }
-
-    largest_set.cloned().context("")
+    Ok(network.group(&clique))
 }
 
 fn group_password(group: &BTreeSet<String>) -> String {
@@ -102,8 +73,8 @@ pub fn run() -> Result<()> {
         "{}/src/day_23/input.txt",
         env!("CARGO_MANIFEST_DIR")
     ))?;
-    let connections = parse_input(&input)?;
-    let groups = group_connections(&connections)?;
+    let network = parse_input(&input)?;
+    let groups = group_connections(&network);
 
     let groups_containing_t = groups
         .iter()
@@ -111,7 +82,7 @@ pub fn run() -> Result<()> {
         .count();
     println!("Day 23, Part 1: Number of groups that contain 't': {groups_containing_t}");
 
-    let largest_group = largest_group(&connections)?;
+    let largest_group = largest_group(&network)?;
     let password = group_password(&largest_group);
     println!("Day 23, Part 2: Password of largest group: {password}");
 
@@ -157,8 +128,8 @@ td-yn";
 
     #[test]
     fn part_one() {
-        let connections = parse_input(INPUT).unwrap();
-        let groups = group_connections(&connections).unwrap();
+        let network = parse_input(INPUT).unwrap();
+        let groups = group_connections(&network);
         let groups_containing_t = groups
             .iter()
             .filter(|g| g.len() >= 3 && g.iter().any(|n| n.starts_with('t')))
@@ -168,8 +139,8 @@ td-yn";
 
     #[test]
     fn part_two() {
-        let connections = parse_input(INPUT).unwrap();
-        let largest_group = largest_group(&connections).unwrap();
+        let network = parse_input(INPUT).unwrap();
+        let largest_group = largest_group(&network).unwrap();
         let password = group_password(&largest_group);
         assert_eq!(password, "co,de,ka,ta".to_string());
     }
@@ -1,8 +1,11 @@
-use anyhow::{bail, Result};
-use regex::Regex;
+use anyhow::{Context, Result};
+
+use scanner::{Call, Semantics};
+
+mod scanner;
 
 struct State {
-    val: i32,
+    val: i64,
     mul_active: bool,
 }
 
@@ -15,47 +18,30 @@ impl Default for State {
     }
 }
 
-enum Op {
-    Mul(i32, i32),
-    Do,
-    Dont,
-}
-
-impl Op {
-    fn exec(&self, state: &mut State, allowance: bool) {
-        match self {
-            Self::Mul(a, b) => {
-                if !allowance || (allowance && state.mul_active) {
-                    state.val += a * b;
+impl State {
+    fn exec(&mut self, call: &Call, allowance: bool) -> Result<()> {
+        match call.spec.semantics {
+            Semantics::Value(_) => {
+                if !allowance || self.mul_active {
+                    self.val = self
+                        .val
+                        .checked_add(call.value()?)
+                        .context("Overflow adding up the results")?;
                 }
             }
-            Self::Do => state.mul_active = true,
-            Self::Dont => state.mul_active = false,
+            Semantics::Toggle(active) => self.mul_active = active,
         }
+        Ok(())
     }
 }
 
-fn parse_input(input: &str) -> Result<Vec<Op>> {
-    let re =
-        Regex::new("(?<mul>mul\\((?<a>[0-9]+),(?<b>[0-9]+)\\))|(?<do>do\\((?<c>\\s*)(?<d>\\s*)\\))|(?<dont>don't\\((?<e>\\s*)(?<f>\\s*)\\))")?;
-
-    let ops: Result<Vec<_>> = re
-        .captures_iter(input)
-        .map(|cap| {
-            if cap.name("mul").is_some() {
-                let (_, [_mul, a, b]) = cap.extract();
-                Ok(Op::Mul(a.parse()?, b.parse()?))
-            } else if cap.name("do").is_some() {
-                Ok(Op::Do)
-            } else if cap.name("dont").is_some() {
-                Ok(Op::Dont)
-            } else {
-                bail!("")
-            }
-        })
-        .collect();
-
-    Ok(ops?)
+/// Sum of all the values, with `allowance` only the ones enabled by `do()` and `don't()`.
+fn evaluate(calls: &[Call], allowance: bool) -> Result<i64> {
+    let mut state = State::default();
+    for call in calls {
+        state.exec(call, allowance)?;
+    }
+    Ok(state.val)
 }
 
 pub fn run() -> Result<()> {
@@ -63,19 +49,50 @@ pub fn run() -> Result<()> {
         "{}/src/day_3/input.txt",
         env!("CARGO_MANIFEST_DIR")
     ))?;
-    let ops = parse_input(&input)?;
+    let scan = scanner::scan(&input, scanner::PUZZLE);
 
-    let state_one = ops.iter().fold(State::default(), |mut state, op| {
-        op.exec(&mut state, false);
-        state
-    });
-    println!("Day 3, Part 1: {}", state_one.val);
+    println!("Day 3, Part 1: {}", evaluate(&scan.calls, false)?);
+    println!("Day 3, Part 2: {}", evaluate(&scan.calls, true)?);
 
-    let state_two = ops.iter().fold(State::default(), |mut state, op| {
-        op.exec(&mut state, true);
-        state
-    });
-    println!("Day 3, Part 2: {}", state_two.val);
+    Ok(())
+}
+
+/// Evaluates the memory with the extended instructions, which also nest.
+pub fn extended() -> Result<()> {
+    let input = std::fs::read_to_string(format!(
+        "{}/src/day_3/input.txt",
+        env!("CARGO_MANIFEST_DIR")
+    ))?;
+    let scan = scanner::scan(&input, scanner::EXTENDED);
+
+    println!("Day 3, Part 1 extended: {}", evaluate(&scan.calls, false)?);
+    println!("Day 3, Part 2 extended: {}", evaluate(&scan.calls, true)?);
+
+    Ok(())
+}
+
+/// Lists the corrupted spans of the memory with their offsets.
+pub fn corrupted() -> Result<()> {
+    let input = std::fs::read_to_string(format!(
+        "{}/src/day_3/input.txt",
+        env!("CARGO_MANIFEST_DIR")
+    ))?;
+    let scan = scanner::scan(&input, scanner::PUZZLE);
+
+    for span in &scan.corrupted {
+        println!(
+            "{:>6}..{:<6} {:?}",
+            span.start,
+            span.end,
+            &input[span.clone()]
+        );
+    }
+    let skipped = scan.corrupted.iter().map(|span| span.len()).sum::<usize>();
+    println!(
+        "{} corrupted spans with {skipped} of {} bytes",
+        scan.corrupted.len(),
+        input.len()
+    );
 
     Ok(())
 }
@@ -91,21 +108,31 @@ mod tests {
 
     #[test]
     fn part_one() {
-        let ops = parse_input(INPUT).unwrap();
-        let state = ops.iter().fold(State::default(), |mut state, op| {
-            op.exec(&mut state, false);
-            state
-        });
-        assert_eq!(state.val, 161);
+        let scan = scanner::scan(INPUT, scanner::PUZZLE);
+        assert_eq!(evaluate(&scan.calls, false).unwrap(), 161);
     }
 
     #[test]
     fn part_two() {
-        let ops = parse_input(INPUT_WITH_DO_DONT).unwrap();
-        let state = ops.iter().fold(State::default(), |mut state, op| {
-            op.exec(&mut state, true);
-            state
-        });
-        assert_eq!(state.val, 48);
+        let scan = scanner::scan(INPUT_WITH_DO_DONT, scanner::PUZZLE);
+        assert_eq!(evaluate(&scan.calls, true).unwrap(), 48);
+    }
+
+    #[test]
+    fn extended() {
+        let input = "mul(add(1,2),3)don't()neg(mul(999,999))do()add(neg(5),mul(2,mul(2,2)))";
+        let scan = scanner::scan(input, scanner::EXTENDED);
+        assert_eq!(evaluate(&scan.calls, false).unwrap(), 9 - 998001 + 3);
+        assert_eq!(evaluate(&scan.calls, true).unwrap(), 12);
+
+        // Products beyond 32 bits are fine, beyond 64 bits they are errors
+        let scan = scanner::scan("mul(99999,99999)mul(99999,99999)", scanner::PUZZLE);
+        assert_eq!(evaluate(&scan.calls, false).unwrap(), 19999600002);
+        let input = "mul(4294967296,1073741824)mul(4294967296,1073741824)";
+        let scan = scanner::scan(input, scanner::PUZZLE);
+        assert_eq!(
+            evaluate(&scan.calls, false).unwrap_err().to_string(),
+            "Overflow adding up the results"
+        );
     }
 }
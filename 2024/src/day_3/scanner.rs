@@ -0,0 +1,292 @@
+use std::fmt::Display;
+use std::ops::Range;
+
+use anyhow::{bail, Context, Result};
+
+/// Type of an instruction argument.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Arg {
+    /// Unsigned decimal number
+    Number,
+    /// Number or a nested call of an instruction computing a value
+    Value,
+}
+
+/// What an instruction does when it is executed.
+#[derive(Clone, Copy)]
+pub(super) enum Semantics {
+    /// Computes a value from its arguments, `None` if it overflows
+    Value(fn(&[i64]) -> Option<i64>),
+    /// Turns the values after it on or off
+    Toggle(bool),
+}
+
+/// Instruction written as `name(arg,arg,..)` without any whitespace.
+pub(super) struct Spec {
+    pub(super) name: &'static str,
+    pub(super) args: &'static [Arg],
+    pub(super) semantics: Semantics,
+}
+
+fn mul(args: &[i64]) -> Option<i64> {
+    args[0].checked_mul(args[1])
+}
+
+fn add(args: &[i64]) -> Option<i64> {
+    args[0].checked_add(args[1])
+}
+
+fn neg(args: &[i64]) -> Option<i64> {
+    args[0].checked_neg()
+}
+
+/// Instructions of the puzzle.
+pub(super) const PUZZLE: &[Spec] = &[
+    Spec {
+        name: "mul",
+        args: &[Arg::Number, Arg::Number],
+        semantics: Semantics::Value(mul),
+    },
+    Spec {
+        name: "do",
+        args: &[],
+        semantics: Semantics::Toggle(true),
+    },
+    Spec {
+        name: "don't",
+        args: &[],
+        semantics: Semantics::Toggle(false),
+    },
+];
+
+/// Instructions of the puzzle with `add` and `neg`, arithmetic can be nested like
+/// `mul(add(1,2),neg(3))`.
+pub(super) const EXTENDED: &[Spec] = &[
+    Spec {
+        name: "mul",
+        args: &[Arg::Value, Arg::Value],
+        semantics: Semantics::Value(mul),
+    },
+    Spec {
+        name: "add",
+        args: &[Arg::Value, Arg::Value],
+        semantics: Semantics::Value(add),
+    },
+    Spec {
+        name: "neg",
+        args: &[Arg::Value],
+        semantics: Semantics::Value(neg),
+    },
+    Spec {
+        name: "do",
+        args: &[],
+        semantics: Semantics::Toggle(true),
+    },
+    Spec {
+        name: "don't",
+        args: &[],
+        semantics: Semantics::Toggle(false),
+    },
+];
+
+pub(super) enum Expr<'a> {
+    Number(i64),
+    Call(Call<'a>),
+}
+
+impl Display for Expr<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Number(value) => write!(f, "{value}"),
+            Self::Call(call) => write!(f, "{call}"),
+        }
+    }
+}
+
+/// Instruction found in the memory with the bytes it spans.
+pub(super) struct Call<'a> {
+    pub(super) spec: &'a Spec,
+    pub(super) args: Vec<Expr<'a>>,
+    pub(super) span: Range<usize>,
+}
+
+impl Call<'_> {
+    /// Value computed by the instruction, fails for toggles and on overflows.
+    pub(super) fn value(&self) -> Result<i64> {
+        let Semantics::Value(eval) = self.spec.semantics else {
+            bail!("{self} at {} does not compute a value", self.span.start);
+        };
+        let args = self
+            .args
+            .iter()
+            .map(|arg| match arg {
+                Expr::Number(value) => Ok(*value),
+                Expr::Call(call) => call.value(),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        eval(&args).with_context(|| format!("Overflow in {self} at {}", self.span.start))
+    }
+}
+
+impl Display for Call<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}(", self.spec.name)?;
+        for (idx, arg) in self.args.iter().enumerate() {
+            if idx > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{arg}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Instructions found in the memory and the corrupted spans between them.
+pub(super) struct Scan<'a> {
+    pub(super) calls: Vec<Call<'a>>,
+    pub(super) corrupted: Vec<Range<usize>>,
+}
+
+struct Scanner<'s, 'a> {
+    input: &'s str,
+    specs: &'a [Spec],
+}
+
+impl<'a> Scanner<'_, 'a> {
+    /// Call of any of the instructions starting at `pos`, the first one that matches wins.
+    fn call(&self, pos: usize) -> Option<Call<'a>> {
+        self.specs.iter().find_map(|spec| self.call_of(spec, pos))
+    }
+
+    fn call_of(&self, spec: &'a Spec, pos: usize) -> Option<Call<'a>> {
+        let mut end = self.expect(spec.name, pos)?;
+        end = self.expect("(", end)?;
+        let mut args = Vec::with_capacity(spec.args.len());
+        for (idx, kind) in spec.args.iter().enumerate() {
+            if idx > 0 {
+                end = self.expect(",", end)?;
+            }
+            let (arg, arg_end) = self.arg(*kind, end)?;
+            args.push(arg);
+            end = arg_end;
+        }
+        end = self.expect(")", end)?;
+        Some(Call {
+            spec,
+            args,
+            span: pos..end,
+        })
+    }
+
+    fn expect(&self, token: &str, pos: usize) -> Option<usize> {
+        self.input[pos..]
+            .starts_with(token)
+            .then_some(pos + token.len())
+    }
+
+    fn arg(&self, kind: Arg, pos: usize) -> Option<(Expr<'a>, usize)> {
+        let rest = &self.input[pos..];
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits > 0 {
+            // Numbers too big for 64 bits are corrupted
+            let value = rest[..digits].parse().ok()?;
+            return Some((Expr::Number(value), pos + digits));
+        }
+        match kind {
+            Arg::Number => None,
+            Arg::Value => {
+                let call = self.call(pos)?;
+                if let Semantics::Toggle(_) = call.spec.semantics {
+                    return None;
+                }
+                let end = call.span.end;
+                Some((Expr::Call(call), end))
+            }
+        }
+    }
+}
+
+/// Finds all the instructions of `specs` in the memory, everything else is corrupted.
+pub(super) fn scan<'a>(input: &str, specs: &'a [Spec]) -> Scan<'a> {
+    let scanner = Scanner { input, specs };
+    let mut calls = Vec::new();
+    let mut corrupted = Vec::new();
+    let mut skipped_from = 0;
+    let mut pos = 0;
+    while let Some(c) = input[pos..].chars().next() {
+        let Some(call) = scanner.call(pos) else {
+            pos += c.len_utf8();
+            continue;
+        };
+        if skipped_from < pos {
+            corrupted.push(skipped_from..pos);
+        }
+        pos = call.span.end;
+        skipped_from = pos;
+        calls.push(call);
+    }
+    if skipped_from < input.len() {
+        corrupted.push(skipped_from..input.len());
+    }
+    Scan { calls, corrupted }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calls(input: &str, specs: &[Spec]) -> Vec<String> {
+        scan(input, specs)
+            .calls
+            .iter()
+            .map(|call| call.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn corrupted_spans() {
+        let input = "xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))";
+        let scan = scan(input, PUZZLE);
+        let spans = scan
+            .calls
+            .iter()
+            .map(|call| call.span.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(spans, [1..9, 29..37, 53..62, 62..70]);
+        assert_eq!(scan.corrupted, [0..1, 9..29, 37..53, 70..71]);
+        assert_eq!(&input[9..29], "%&mul[3,7]!@^do_not_");
+    }
+
+    #[test]
+    fn nested_calls() {
+        let input = "mul(add(1,2),neg(3))+add(mul(2,2),do())neg(neg(7)";
+        assert_eq!(calls(input, PUZZLE), ["mul(2,2)", "do()"]);
+        assert_eq!(
+            calls(input, EXTENDED),
+            ["mul(add(1,2),neg(3))", "mul(2,2)", "do()", "neg(7)"]
+        );
+
+        let scan = scan(input, EXTENDED);
+        assert_eq!(scan.calls[0].value().unwrap(), -9);
+        assert_eq!(scan.calls[0].span, 0..20);
+        assert_eq!(
+            scan.calls[2].value().unwrap_err().to_string(),
+            "do() at 34 does not compute a value"
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        let scan = scan(
+            "mul(4294967296,4294967296)mul(99999999999999999999,1)",
+            PUZZLE,
+        );
+        assert_eq!(scan.calls.len(), 1);
+        assert_eq!(
+            format!("{:#}", scan.calls[0].value().unwrap_err()),
+            "Overflow in mul(4294967296,4294967296) at 0"
+        );
+        assert_eq!(scan.corrupted.len(), 1);
+        assert_eq!(scan.corrupted[0], 26..53);
+    }
+}
@@ -1,11 +1,10 @@
 use regex::Regex;
-use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet};
 
 use anyhow::{Context, Result};
+use aoc_common::graph::{CycleError, DirectedGraph};
 
-fn parse_input(input: &str) -> Result<(HashMap<usize, HashSet<usize>>, Vec<Vec<usize>>)> {
-    let mut rules = HashMap::<usize, HashSet<usize>>::default();
+fn parse_input(input: &str) -> Result<(DirectedGraph, Vec<Vec<usize>>)> {
+    let mut rules = Vec::default();
     let mut pages = Vec::default();
 
     let rule_re = Regex::new("^(?<first>\\d+)\\|(?<sec>\\d+)$")?;
@@ -16,10 +15,7 @@ fn parse_input(input: &str) -> Result<(HashMap<usize, HashSet<usize>>, Vec<Vec<u
         if rule_re.is_match(line) {
             let cap = rule_re.captures(line).context("Capture rules failed")?;
             let (_, [first, sec]) = cap.extract();
-            rules
-                .entry(first.parse()?)
-                .or_default()
-                .insert(sec.parse()?);
+            rules.push((first.parse::<usize>()?, sec.parse::<usize>()?));
         } else if page_re.is_match(line) {
             let v: Vec<usize> = line
                 .trim()
@@ -30,47 +26,43 @@ fn parse_input(input: &str) -> Result<(HashMap<usize, HashSet<usize>>, Vec<Vec<u
         }
     }
 
-    Ok((rules, pages))
-}
-
-fn is_valid_order(page_list: &[usize], rules: &HashMap<usize, HashSet<usize>>) -> bool {
-    let mut visited = HashSet::<usize>::new();
-
-    for page in page_list {
-        if let Some(only_after) = rules.get(page) {
-            for oa in only_after {
-                if visited.contains(oa) {
-                    return false;
-                }
-            }
-        }
-
-        visited.insert(*page);
+    // Page numbers are the nodes, a rule `a|b` is the edge a -> b
+    let max_page = rules
+        .iter()
+        .flat_map(|(a, b)| [a, b])
+        .chain(pages.iter().flatten())
+        .max()
+        .map_or(0, |max| max + 1);
+    let mut graph = DirectedGraph::new(max_page);
+    for (first, sec) in rules {
+        graph.add_edge(first, sec);
     }
 
-    true
+    Ok((graph, pages))
 }
 
-fn compare(a: &usize, b: &usize, rules: &HashMap<usize, HashSet<usize>>) -> Ordering {
-    match rules.get(&a) {
-        Some(r) => {
-            if r.contains(&b) {
-                Ordering::Less
-            } else {
-                Ordering::Equal
-            }
-        }
-        None => match rules.get(&b) {
-            Some(r) => {
-                if r.contains(&a) {
-                    Ordering::Greater
-                } else {
-                    Ordering::Equal
-                }
-            }
-            None => Ordering::Equal,
-        },
+fn is_valid_order(page_list: &[usize], rules: &DirectedGraph) -> bool {
+    page_list.iter().enumerate().all(|(idx, page)| {
+        page_list[..idx]
+            .iter()
+            .all(|prev| !rules.has_edge(*page, *prev))
+    })
+}
+
+/// Orders the pages by the rules that apply to them, which have to fix the order completely
+fn sort_pages(page_list: &[usize], rules: &DirectedGraph) -> Result<Vec<usize>> {
+    let sorted = rules
+        .induced_subgraph(page_list)
+        .topological_sort()
+        // The subgraph numbers its nodes by their position in the page list
+        .map_err(|err| CycleError {
+            nodes: err.nodes.iter().map(|idx| page_list[*idx]).collect(),
+        })
+        .with_context(|| format!("Rules for {page_list:?} contradict each other"))?;
+    if !sorted.unique {
+        anyhow::bail!("Rules do not fix the order of {page_list:?}");
     }
+    Ok(sorted.order.into_iter().map(|idx| page_list[idx]).collect())
 }
 
 pub fn run() -> Result<()> {
@@ -78,7 +70,7 @@ pub fn run() -> Result<()> {
         "{}/src/day_5/input.txt",
         env!("CARGO_MANIFEST_DIR")
     ))?;
-    let (rules, mut pages) = parse_input(&input).unwrap();
+    let (rules, pages) = parse_input(&input).unwrap();
 
     let mut page_numbers = 0;
     for pl in pages.iter() {
@@ -89,10 +81,10 @@ pub fn run() -> Result<()> {
     println!("Day 5, Part 1: Page numbers: {page_numbers}");
 
     let mut page_numbers_incorrect = 0;
-    for pl in pages.iter_mut() {
+    for pl in pages.iter() {
         if !is_valid_order(pl, &rules) {
-            pl.sort_by(|a, b| compare(a, b, &rules));
-            page_numbers_incorrect += pl[pl.len() / 2];
+            let sorted = sort_pages(pl, &rules)?;
+            page_numbers_incorrect += sorted[sorted.len() / 2];
         }
     }
     println!("Day 5, Part 2: Page numbers of sorted incorrect entries: {page_numbers_incorrect}");
@@ -148,15 +140,30 @@ mod tests {
 
     #[test]
     fn part_two() {
-        let (rules, mut pages) = parse_input(INPUT).unwrap();
+        let (rules, pages) = parse_input(INPUT).unwrap();
         let mut page_numbers = 0;
-        for pl in pages.iter_mut() {
+        for pl in pages.iter() {
             if !is_valid_order(pl, &rules) {
-                pl.sort_by(|a, b| compare(a, b, &rules));
-                page_numbers += pl[pl.len() / 2];
+                let sorted = sort_pages(pl, &rules).unwrap();
+                page_numbers += sorted[sorted.len() / 2];
             }
         }
 
         assert_eq!(page_numbers, 123);
     }
+
+    #[test]
+    fn contradicting_rules() {
+        let (rules, pages) = parse_input("1|2\n2|3\n3|1\n1|4\n\n1,2,3\n4,1").unwrap();
+        let err = sort_pages(&pages[0], &rules).unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "Rules for [1, 2, 3] contradict each other: Graph contains the cycle 1 -> 2 -> 3 -> 1"
+        );
+        assert!(sort_pages(&pages[1], &rules).unwrap() == vec![1, 4]);
+
+        // Without a rule between 2 and 3 their order is not fixed
+        let (rules, pages) = parse_input("1|2\n1|3\n\n3,2,1").unwrap();
+        assert!(sort_pages(&pages[0], &rules).is_err());
+    }
 }
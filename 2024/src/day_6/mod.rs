@@ -1,4 +1,5 @@
 use anyhow::{bail, Context, Result};
+use aoc_common::cycle;
 use std::collections::HashSet;
 
 #[derive(Eq, PartialEq, Hash, Clone, Copy)]
@@ -53,48 +54,48 @@ fn get_dir(pos: char) -> Option<Direction> {
     }
 }
 
-fn positions_visited(mace: &[Vec<char>]) -> Result<usize> {
-    let mut visited = HashSet::<(usize, usize)>::new();
-
-    let mut loop_visited = HashSet::<(usize, usize, Direction)>::new();
-
-    let mut dir = Direction::Up;
-    let mut pos = (0, 0);
-    'outer: for i in 0..mace.len() {
-        for j in 0..mace[i].len() {
-            if let Some(found_dir) = get_dir(mace[i][j]) {
-                dir = found_dir;
-                pos = (i, j);
-                visited.insert((i, j));
-                break 'outer;
+fn start(mace: &[Vec<char>]) -> ((usize, usize), Direction) {
+    for (i, row) in mace.iter().enumerate() {
+        for (j, cell) in row.iter().enumerate() {
+            if let Some(dir) = get_dir(*cell) {
+                return ((i, j), dir);
             }
         }
     }
+    ((0, 0), Direction::Up)
+}
 
-    loop {
-        if let Ok(new_pos) = dir.apply(pos, (mace.len(), mace[0].len())) {
-            if mace[new_pos.0][new_pos.1] == '#' {
-                dir = dir.turn();
+fn next_state(
+    mace: &[Vec<char>],
+    (pos, dir): ((usize, usize), Direction),
+) -> Option<((usize, usize), Direction)> {
+    let new_pos = dir.apply(pos, (mace.len(), mace[0].len())).ok()?;
+    if mace[new_pos.0][new_pos.1] == '#' {
+        Some((pos, dir.turn()))
+    } else {
+        Some((new_pos, dir))
+    }
+}
 
-                let loop_tup = (pos.0, pos.1, dir);
-                if loop_visited.contains(&loop_tup) {
-                    bail!("Loop detected");
-                }
-                loop_visited.insert(loop_tup);
-            } else {
-                pos = new_pos;
-                visited.insert(new_pos);
-
-                let loop_tup = (pos.0, pos.1, dir);
-                if loop_visited.contains(&loop_tup) {
-                    bail!("Loop detected");
-                }
-                loop_visited.insert(loop_tup);
-            }
-        } else {
-            return Ok(visited.len());
-        }
+fn is_loop(mace: &[Vec<char>]) -> bool {
+    // Leaving the mace is modelled as the state `None` that never changes again, so every walk
+    // ends in a cycle. A guard that is still inside always moves or turns, so only a cycle of
+    // length one is the guard having left the mace.
+    let walk = cycle::brent(Some(start(mace)), |state| {
+        state.and_then(|state| next_state(mace, state))
+    });
+    walk.len > 1
+}
+
+fn positions_visited(mace: &[Vec<char>]) -> Result<usize> {
+    if is_loop(mace) {
+        bail!("Loop detected");
     }
+
+    let visited = std::iter::successors(Some(start(mace)), |state| next_state(mace, *state))
+        .map(|(pos, _)| pos)
+        .collect::<HashSet<_>>();
+    Ok(visited.len())
 }
 
 fn loop_positions(mace: &[Vec<char>]) -> usize {
@@ -107,7 +108,7 @@ fn loop_positions(mace: &[Vec<char>]) -> usize {
             if tmp != '#' {
                 mace[i][j] = '#';
 
-                if positions_visited(&mace).is_err() {
+                if is_loop(&mace) {
                     loop_locations += 1;
                 }
 
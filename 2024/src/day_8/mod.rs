@@ -1,4 +1,5 @@
 use anyhow::Result;
+use aoc_common::number_theory::gcd;
 use std::collections::{HashMap, HashSet};
 
 fn parse_input(input: &str) -> Result<(HashMap<char, HashSet<(isize, isize)>>, (isize, isize))> {
@@ -76,23 +77,20 @@ fn harmonic_antinodes(
                 if i == j {
                     continue;
                 }
-                let v = (b_pos.0 - a_pos.0, b_pos.1 - a_pos.1);
-
-                for i in 1..=isize::MAX {
-                    let n = (a_pos.0 + v.0 * i, a_pos.1 + v.1 * i);
-                    if in_bounds(&n, bounds) {
-                        antinodes.insert(n);
-                    } else {
-                        break;
-                    }
-                }
-
-                for i in 1..=isize::MAX {
-                    let n = (b_pos.0 - v.0 * i, b_pos.1 - v.1 * i);
-                    if in_bounds(&n, bounds) {
-                        antinodes.insert(n);
-                    } else {
-                        break;
+                // Reduce the distance by the gcd so every grid position on the line is hit, not
+                // only the multiples of the distance between both antennas
+                let (dx, dy) = (b_pos.0 - a_pos.0, b_pos.1 - a_pos.1);
+                let divisor = gcd(dx as i128, dy as i128) as isize;
+                let v = (dx / divisor, dy / divisor);
+
+                for dir in [1, -1] {
+                    for i in 0..=isize::MAX {
+                        let n = (a_pos.0 + dir * v.0 * i, a_pos.1 + dir * v.1 * i);
+                        if in_bounds(&n, bounds) {
+                            antinodes.insert(n);
+                        } else {
+                            break;
+                        }
                     }
                 }
             }
@@ -34,7 +34,12 @@ fn main() -> Result<()> {
         match day {
             1 => day_1::run()?,
             2 => day_2::run()?,
-            3 => day_3::run()?,
+            3 => match std::env::args().nth(2).as_deref() {
+                Some("extended") => day_3::extended()?,
+                Some("corrupted") => day_3::corrupted()?,
+                Some(cmd) => anyhow::bail!("Unknown command {cmd} for day 3"),
+                None => day_3::run()?,
+            },
             4 => day_4::run()?,
             5 => day_5::run()?,
             6 => day_6::run()?,
@@ -48,7 +53,14 @@ fn main() -> Result<()> {
             14 => day_14::run()?,
             15 => day_15::run()?,
             16 => day_16::run()?,
-            17 => day_17::run()?,
+            17 => match std::env::args().nth(2).as_deref() {
+                Some("disasm") => day_17::disasm()?,
+                Some("debug") => day_17::debug()?,
+                Some("quines") => day_17::quines()?,
+                Some("asm") => day_17::asm(std::env::args().nth(3))?,
+                Some(cmd) => anyhow::bail!("Unknown command {cmd} for day 17"),
+                None => day_17::run()?,
+            },
             18 => day_18::run()?,
             19 => day_19::run()?,
             20 => day_20::run()?,
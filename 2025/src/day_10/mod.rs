@@ -1,6 +1,6 @@
 use crate::Day;
 use anyhow::{Context, Result};
-use std::collections::{HashMap, HashSet, VecDeque};
+use aoc_common::linear_algebra::{BitVector, Gf2Matrix, IntegerProgram, Matrix};
 use std::path::Path;
 
 pub(crate) struct DayTen {
@@ -23,9 +23,10 @@ impl Day for DayTen {
             .lines()
             .map(MachineInstruction::try_from)
             .collect::<Result<Vec<_>>>()?;
-        let fewest_presses = machine_instructions.iter().fold(0, |acc, m| {
-            m.set_desired_lights().map(|presses| acc + presses).unwrap_or(acc)
-        });
+        let fewest_presses = machine_instructions
+            .iter()
+            .map(MachineInstruction::set_desired_lights)
+            .sum::<Result<usize>>()?;
         println!("Day 10 - Part 1: Sum of fewest presses to configure lights: {fewest_presses}");
         Ok(())
     }
@@ -36,9 +37,10 @@ impl Day for DayTen {
             .lines()
             .map(MachineInstruction::try_from)
             .collect::<Result<Vec<_>>>()?;
-        let fewest_presses = machine_instructions.iter().fold(0, |acc, m| {
-            m.set_joltage_levels().map(|presses| acc + presses).unwrap_or(acc)
-        });
+        let fewest_presses = machine_instructions
+            .iter()
+            .map(MachineInstruction::set_joltage_levels)
+            .sum::<Result<usize>>()?;
         println!(
             "Day 10 - Part 2: Sum of fewest presses to configure joltage levels: {fewest_presses}"
         );
@@ -52,15 +54,6 @@ enum LightStatus {
     Off,
 }
 
-impl LightStatus {
-    fn toggle(&mut self) {
-        *self = match self {
-            Self::On => Self::Off,
-            Self::Off => Self::On,
-        };
-    }
-}
-
 impl TryFrom<char> for LightStatus {
     type Error = anyhow::Error;
 
@@ -78,14 +71,6 @@ struct Button {
     lights_to_change: Vec<usize>,
 }
 
-impl Button {
-    fn push(&self, lights: &mut [LightStatus]) {
-        for light_index in self.lights_to_change.iter() {
-            lights[*light_index].toggle();
-        }
-    }
-}
-
 impl TryFrom<&str> for Button {
     type Error = anyhow::Error;
 
@@ -119,112 +104,81 @@ struct MachineInstruction {
 }
 
 impl MachineInstruction {
-    fn set_desired_lights(&self) -> Option<usize> {
-        struct QueueState {
-            light_status: Vec<LightStatus>,
-            presses_until: usize,
-        }
-
-        let mut queue = VecDeque::from([QueueState {
-            light_status: vec![LightStatus::Off; self.desired_lights.len()],
-            presses_until: 0_usize,
-        }]);
-        let mut seen = HashSet::new();
-
-        while let Some(curr) = queue.pop_front() {
-            if curr.light_status == self.desired_lights {
-                return Some(curr.presses_until);
-            }
-
-            if seen.contains(&curr.light_status) {
-                continue;
-            }
-            seen.insert(curr.light_status.clone());
-
-            for next_button in self.buttons.iter() {
-                let mut next_light_status = curr.light_status.clone();
-                next_button.push(&mut next_light_status);
-                queue.push_back(QueueState {
-                    light_status: next_light_status,
-                    presses_until: curr.presses_until + 1,
-                });
-            }
-        }
-
-        None
-    }
-
-    pub fn set_joltage_levels(&self) -> Option<usize> {
-        let num_vars = self.joltage_levels.len();
-
-        // Pre-calculate all 2^n combinations of button presses (the 'parity' combinations)
-        // This is safe because Advent of Code typically keeps the number of buttons small
-        let mut combinations = Vec::new();
-        let num_buttons = self.buttons.len();
-
-        for i in 0..(1 << num_buttons) {
-            let mut combo_effect = vec![0; num_vars];
-            let mut presses = 0;
-            for j in 0..num_buttons {
-                if (i >> j) & 1 == 1 {
-                    presses += 1;
-                    for &light in &self.buttons[j].lights_to_change {
-                        combo_effect[light] += 1;
-                    }
-                }
-            }
-            combinations.push((combo_effect, presses));
-        }
+    fn set_desired_lights(&self) -> Result<usize> {
+        // Pressing a button twice cancels out, so every button is pressed at most once and each
+        // light has to be toggled by an odd number of pressed buttons exactly if it should be on
+        let lights = self.desired_lights.len();
+        let toggles = Gf2Matrix::from_rows(
+            self.buttons.len(),
+            (0..lights).map(|light| {
+                BitVector::from_ones(
+                    self.buttons.len(),
+                    self.buttons
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, button)| button.lights_to_change.contains(&light))
+                        .map(|(idx, _)| idx),
+                )
+            }),
+        );
+        let desired = BitVector::from_ones(
+            lights,
+            self.desired_lights
+                .iter()
+                .enumerate()
+                .filter(|(_, status)| **status == LightStatus::On)
+                .map(|(idx, _)| idx),
+        );
 
-        let mut cache = HashMap::new();
-        self.solve_recursive(&self.joltage_levels, &combinations, &mut cache)
+        let presses = toggles
+            .solve(&desired)
+            .with_context(|| {
+                format!(
+                    "No combination of button presses reaches the lights {:?}",
+                    self.desired_lights
+                )
+            })?
+            .min_weight();
+        Ok(presses.count_ones())
     }
 
-    fn solve_recursive(
-        &self,
-        current_target: &[usize],
-        combinations: &Vec<(Vec<usize>, usize)>,
-        cache: &mut HashMap<Vec<usize>, Option<usize>>
-    ) -> Option<usize> {
-        // Base case: all counters are zero
-        if current_target.iter().all(|&x| x == 0) {
-            return Some(0);
-        }
-
-        if let Some(&cached) = cache.get(current_target) {
-            return cached;
-        }
-
-        let mut min_presses = None;
-
-        for (combo_effect, combo_presses) in combinations {
-            // Check if this combination matches the parity of every counter
-            let mut possible = true;
-            for i in 0..current_target.len() {
-                if current_target[i] < combo_effect[i] || (current_target[i] % 2 != combo_effect[i] % 2) {
-                    possible = false;
-                    break;
-                }
-            }
-
-            if possible {
-                // Determine the next state: (Target - Combo) / 2
-                let next_state: Vec<usize> = current_target.iter()
-                    .zip(combo_effect.iter())
-                    .map(|(t, c)| (t - c) / 2)
-                    .collect();
-
-                if let Some(sub_presses) = self.solve_recursive(&next_state, combinations, cache) {
-                    let total = 2 * sub_presses + combo_presses;
-                    if min_presses.is_none() || total < min_presses.unwrap() {
-                        min_presses = Some(total);
-                    }
-                }
-            }
-        }
-
-        cache.insert(current_target.to_vec(), min_presses);
-        min_presses
+    fn set_joltage_levels(&self) -> Result<usize> {
+        // Every counter is a linear equation over the number of presses of the buttons that
+        // increase it: sum(presses[b] for b in buttons touching counter) = joltage_level
+        let equations = Matrix::from_rows((0..self.joltage_levels.len()).map(|counter| {
+            self.buttons
+                .iter()
+                .map(move |button| i128::from(button.lights_to_change.contains(&counter)))
+        }));
+        let levels = self
+            .joltage_levels
+            .iter()
+            .map(|level| *level as i128)
+            .collect::<Vec<_>>();
+
+        // A button can not be pressed more often than the lowest counter it increases allows
+        let max_presses = self
+            .buttons
+            .iter()
+            .map(|button| {
+                button
+                    .lights_to_change
+                    .iter()
+                    .map(|counter| levels.get(*counter).copied().unwrap_or(0))
+                    .min()
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<_>>();
+
+        let (presses, _) = IntegerProgram::new(equations, &levels, &max_presses)
+            .minimize(&vec![1; self.buttons.len()])
+            .with_context(|| {
+                format!(
+                    "No combination of button presses reaches the joltage levels {:?}",
+                    self.joltage_levels
+                )
+            })?;
+        Ok(presses as usize)
     }
 }
 
@@ -313,9 +267,11 @@ mod test {
             .map(MachineInstruction::try_from)
             .collect::<Result<Vec<_>>>()
             .unwrap();
-        let fewest_presses = machine_instructions.iter().fold(0, |acc, m| {
-            m.set_desired_lights().map(|presses| acc + presses).unwrap_or(acc)
-        });
+        let fewest_presses = machine_instructions
+            .iter()
+            .map(MachineInstruction::set_desired_lights)
+            .sum::<Result<usize>>()
+            .unwrap();
         assert_eq!(fewest_presses, 7);
     }
 
@@ -326,9 +282,18 @@ mod test {
             .map(MachineInstruction::try_from)
             .collect::<Result<Vec<_>>>()
             .unwrap();
-        let fewest_presses = machine_instructions.iter().fold(0, |acc, m| {
-            m.set_joltage_levels().map(|presses| acc + presses).unwrap_or(acc)
-        });
+        let fewest_presses = machine_instructions
+            .iter()
+            .map(MachineInstruction::set_joltage_levels)
+            .sum::<Result<usize>>()
+            .unwrap();
         assert_eq!(fewest_presses, 33);
     }
+
+    #[test]
+    fn unreachable_joltage_levels() {
+        // No button increases the second counter
+        let machine = MachineInstruction::try_from("[..] (0) {1,1}").unwrap();
+        assert!(machine.set_joltage_levels().is_err());
+    }
 }
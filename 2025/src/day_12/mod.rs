@@ -1,6 +1,6 @@
 use crate::Day;
 use anyhow::{Context, Result};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
 pub(crate) struct DayTwelve {
@@ -18,11 +18,13 @@ impl Day for DayTwelve {
     }
 
     fn part_one(&self) -> Result<()> {
-        let (shapes, tree_areas) = parse_input(&self.input).unwrap();
+        let (shapes, tree_areas) = parse_input(&self.input)?;
         let ok_areas = tree_areas
             .iter()
-            .map(|area| area.ok_heuristically(&shapes))
-            .filter(|ok| *ok)
+            .map(|area| area.pack(&shapes))
+            .collect::<Result<Vec<_>>>()?
+            .iter()
+            .filter(|placement| placement.is_some())
             .count();
         println!("Day 12 - Part 1: Sum areas that are ok: {ok_areas}");
         Ok(())
@@ -34,10 +36,64 @@ impl Day for DayTwelve {
     }
 }
 
+type Cell = (usize, usize);
+
 #[derive(Debug)]
 struct Shape {
     idx: usize,
     space_needed: usize,
+    /// Occupied `(row, col)` cells
+    cells: Vec<Cell>,
+}
+
+impl Shape {
+    /// All distinct rotations and reflections, every one shifted to the top left corner and
+    /// sorted in reading order
+    fn orientations(&self) -> Vec<Vec<Cell>> {
+        let mut orientations = (0..8)
+            .map(|symmetry| transform(&self.cells, symmetry))
+            .collect::<Vec<_>>();
+        orientations.sort();
+        orientations.dedup();
+        orientations
+    }
+
+    /// Height and width of the bounding box
+    fn bounds(&self) -> (usize, usize) {
+        let rows = self.cells.iter().map(|(r, _)| r + 1).max().unwrap_or(0);
+        let cols = self.cells.iter().map(|(_, c)| c + 1).max().unwrap_or(0);
+        (rows, cols)
+    }
+}
+
+/// Applies one of the 8 symmetries of a square: bit 0 mirrors the rows, bit 1 the columns and
+/// bit 2 swaps rows and columns. The first 4 are the symmetries of any rectangle.
+fn transform(cells: &[Cell], symmetry: usize) -> Vec<Cell> {
+    normalize(cells.iter().map(|(r, c)| {
+        let (mut r, mut c) = (*r as isize, *c as isize);
+        if symmetry & 1 == 1 {
+            r = -r;
+        }
+        if symmetry & 2 == 2 {
+            c = -c;
+        }
+        if symmetry & 4 == 4 {
+            (r, c) = (c, r);
+        }
+        (r, c)
+    }))
+}
+
+fn normalize(cells: impl Iterator<Item = (isize, isize)>) -> Vec<Cell> {
+    let cells = cells.collect::<Vec<_>>();
+    let min_r = cells.iter().map(|(r, _)| *r).min().unwrap_or(0);
+    let min_c = cells.iter().map(|(_, c)| *c).min().unwrap_or(0);
+    let mut cells = cells
+        .into_iter()
+        .map(|(r, c)| ((r - min_r) as usize, (c - min_c) as usize))
+        .collect::<Vec<_>>();
+    cells.sort();
+    cells
 }
 
 impl TryFrom<&str> for Shape {
@@ -50,11 +106,21 @@ impl TryFrom<&str> for Shape {
         // .##
 
         let (idx, rest) = value.split_once(":\n").context("")?;
-        let space_needed = rest.chars().filter(|c| *c == '#').count();
+        let cells = rest
+            .lines()
+            .enumerate()
+            .flat_map(|(r, line)| {
+                line.chars()
+                    .enumerate()
+                    .filter(|(_, c)| *c == '#')
+                    .map(move |(c, _)| (r, c))
+            })
+            .collect::<Vec<_>>();
 
         Ok(Self {
             idx: idx.parse::<usize>()?,
-            space_needed,
+            space_needed: cells.len(),
+            cells,
         })
     }
 }
@@ -92,6 +158,255 @@ impl TreeArea {
 
         area_size >= space_needed
     }
+
+    /// Finds a placement of all required presents, `None` if there is none.
+    ///
+    /// The heuristic above rules out areas that are too small. Areas that are large enough to
+    /// give every present its own bounding box are packed right away, everything else is
+    /// decided by an exhaustive search.
+    fn pack(&self, shapes: &[Shape]) -> Result<Option<Placement>> {
+        let needed = self.needed(shapes)?;
+        if !self.ok_heuristically(shapes) {
+            return Ok(None);
+        }
+        if let Some(placement) = self.pack_in_boxes(&needed) {
+            return Ok(Some(placement));
+        }
+        Ok(Packer::new(self.area, &needed).solve())
+    }
+
+    /// Required shapes with their counts, ordered by their index
+    fn needed<'s>(&self, shapes: &'s [Shape]) -> Result<Vec<(&'s Shape, usize)>> {
+        let mut needed = Vec::new();
+        for (idx, count) in &self.presents_needed {
+            if *count > 0 {
+                let shape = shapes
+                    .iter()
+                    .find(|shape| shape.idx == *idx)
+                    .with_context(|| format!("Unknown shape {idx}"))?;
+                needed.push((shape, *count));
+            }
+        }
+        needed.sort_by_key(|(shape, _)| shape.idx);
+        Ok(needed)
+    }
+
+    /// Places every present in its own cell of a grid of equally sized boxes
+    fn pack_in_boxes(&self, needed: &[(&Shape, usize)]) -> Option<Placement> {
+        let (box_rows, box_cols) = needed.iter().fold((0, 0), |(rows, cols), (shape, _)| {
+            let (r, c) = shape.bounds();
+            (rows.max(r), cols.max(c))
+        });
+        let (width, height) = self.area;
+        let per_row = width.checked_div(box_cols)?;
+        let boxes = per_row * height.checked_div(box_rows)?;
+        let presents = needed.iter().map(|(_, count)| count).sum::<usize>();
+        if presents > boxes {
+            return None;
+        }
+
+        let presents = needed
+            .iter()
+            .flat_map(|(shape, count)| std::iter::repeat_n(*shape, *count));
+        let pieces = presents
+            .enumerate()
+            .map(|(i, shape)| {
+                let (top, left) = (i / per_row * box_rows, i % per_row * box_cols);
+                PlacedShape {
+                    shape: shape.idx,
+                    cells: shape
+                        .cells
+                        .iter()
+                        .map(|(r, c)| (top + r, left + c))
+                        .collect(),
+                }
+            })
+            .collect();
+        Some(Placement { pieces })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PlacedShape {
+    shape: usize,
+    cells: Vec<Cell>,
+}
+
+/// Presents placed in an area without overlapping each other
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Placement {
+    pieces: Vec<PlacedShape>,
+}
+
+/// Backtracking search that always fills the first empty cell in reading order.
+///
+/// That cell is either covered by a present whose first cell in reading order lands on it, or
+/// stays empty for good. Identical presents are tracked as a count, so the search never tries
+/// permutations of them. A branch is cut once more cells can no longer be covered by any
+/// remaining present than may stay empty, and states that failed once are remembered.
+struct Packer {
+    width: usize,
+    occupied: Vec<bool>,
+    /// Every way to put a shape into the area as its index and the covered cells in reading order
+    placements: Vec<(usize, Vec<usize>)>,
+    /// Placements by the first cell they cover
+    starting_at: Vec<Vec<usize>>,
+    shape_ids: Vec<usize>,
+    remaining: Vec<usize>,
+    /// Number of cells that may still stay empty
+    slack: usize,
+    placed: Vec<usize>,
+    /// Occupied cells and remaining presents of states that are known to fail
+    dead_ends: HashSet<(Vec<bool>, Vec<usize>)>,
+}
+
+impl Packer {
+    fn new(area: (usize, usize), needed: &[(&Shape, usize)]) -> Self {
+        let (width, height) = area;
+        let space_needed = needed
+            .iter()
+            .map(|(shape, count)| shape.space_needed * count)
+            .sum::<usize>();
+
+        // Mirroring or rotating a solution gives another solution, so a present that is needed
+        // exactly once only has to be tried in one orientation per symmetry class of the area
+        let symmetries = if width == height { 8 } else { 4 };
+        let unique = needed
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, count))| *count == 1)
+            .max_by_key(|(_, (present, _))| present.orientations().len())
+            .map(|(shape, _)| shape);
+
+        let mut placements = Vec::new();
+        let mut starting_at = vec![Vec::new(); width * height];
+        for (shape, (present, _)) in needed.iter().enumerate() {
+            let mut orientations = present.orientations();
+            if unique == Some(shape) {
+                orientations.retain(|cells| {
+                    (0..symmetries).all(|symmetry| *cells <= transform(cells, symmetry))
+                });
+            }
+            for orientation in orientations {
+                let (rows, cols) = orientation.iter().fold((0, 0), |(rows, cols), (r, c)| {
+                    (rows.max(r + 1), cols.max(c + 1))
+                });
+                for top in 0..(height + 1).saturating_sub(rows) {
+                    for left in 0..(width + 1).saturating_sub(cols) {
+                        let cells = orientation
+                            .iter()
+                            .map(|(r, c)| (top + r) * width + left + c)
+                            .collect::<Vec<_>>();
+                        starting_at[cells[0]].push(placements.len());
+                        placements.push((shape, cells));
+                    }
+                }
+            }
+        }
+
+        Self {
+            width,
+            occupied: vec![false; width * height],
+            placements,
+            starting_at,
+            shape_ids: needed.iter().map(|(shape, _)| shape.idx).collect(),
+            remaining: needed.iter().map(|(_, count)| *count).collect(),
+            slack: (width * height).saturating_sub(space_needed),
+            placed: Vec::new(),
+            dead_ends: HashSet::new(),
+        }
+    }
+
+    fn solve(mut self) -> Option<Placement> {
+        if !self.search(0) {
+            return None;
+        }
+        let pieces = self
+            .placed
+            .iter()
+            .map(|placement| {
+                let (shape, cells) = &self.placements[*placement];
+                PlacedShape {
+                    shape: self.shape_ids[*shape],
+                    cells: cells
+                        .iter()
+                        .map(|pos| (pos / self.width, pos % self.width))
+                        .collect(),
+                }
+            })
+            .collect();
+        Some(Placement { pieces })
+    }
+
+    fn fits(&self, placement: usize) -> bool {
+        let (shape, cells) = &self.placements[placement];
+        self.remaining[*shape] > 0 && cells.iter().all(|pos| !self.occupied[*pos])
+    }
+
+    /// Number of empty cells that no remaining present fits on anymore
+    fn uncoverable(&self) -> usize {
+        let mut coverable = self.occupied.clone();
+        for placement in 0..self.placements.len() {
+            if self.fits(placement) {
+                for pos in &self.placements[placement].1 {
+                    coverable[*pos] = true;
+                }
+            }
+        }
+        coverable.iter().filter(|covered| !**covered).count()
+    }
+
+    fn set(&mut self, placement: usize, occupied: bool) {
+        let (shape, cells) = &self.placements[placement];
+        for pos in cells {
+            self.occupied[*pos] = occupied;
+        }
+        if occupied {
+            self.remaining[*shape] -= 1;
+        } else {
+            self.remaining[*shape] += 1;
+        }
+    }
+
+    fn search(&mut self, start: usize) -> bool {
+        if self.remaining.iter().all(|count| *count == 0) {
+            return true;
+        }
+        let Some(pos) = (start..self.occupied.len()).find(|pos| !self.occupied[*pos]) else {
+            return false;
+        };
+        let state = (self.occupied.clone(), self.remaining.clone());
+        if self.dead_ends.contains(&state) || self.uncoverable() > self.slack {
+            return false;
+        }
+
+        for idx in 0..self.starting_at[pos].len() {
+            let placement = self.starting_at[pos][idx];
+            if !self.fits(placement) {
+                continue;
+            }
+            self.set(placement, true);
+            self.placed.push(placement);
+            if self.search(pos + 1) {
+                return true;
+            }
+            self.placed.pop();
+            self.set(placement, false);
+        }
+
+        if self.slack > 0 {
+            self.slack -= 1;
+            self.occupied[pos] = true;
+            let found = self.search(pos + 1);
+            self.occupied[pos] = false;
+            self.slack += 1;
+            if found {
+                return true;
+            }
+        }
+        self.dead_ends.insert(state);
+        false
+    }
 }
 
 impl TryFrom<&str> for TreeArea {
@@ -147,6 +462,7 @@ fn parse_input(input: &str) -> Result<(Vec<Shape>, Vec<TreeArea>)> {
 #[cfg(test)]
 mod test {
     use super::*;
+    use aoc_common::exact_cover::ExactCover;
 
     static INPUT: &str = r#"0:
 ###
@@ -186,11 +502,67 @@ mod test {
     #[test]
     fn part_one() {
         let (shapes, tree_areas) = parse_input(INPUT).unwrap();
-        let _ok_areas = tree_areas
+        let ok_areas = tree_areas
             .iter()
-            .map(|area| area.ok_heuristically(&shapes))
-            .filter(|ok| *ok)
+            .map(|area| area.pack(&shapes).unwrap())
+            .filter(|placement| placement.is_some())
             .count();
-        // assert_eq!(ok_areas, 2);
+        assert_eq!(ok_areas, 2);
+    }
+
+    #[test]
+    fn dancing_links_agrees() {
+        let (shapes, tree_areas) = parse_input(INPUT).unwrap();
+
+        // The last sample area takes too long without telling identical presents apart, so use
+        // some smaller ones instead
+        let extra = ["5x5: 0 0 0 0 3 0", "6x5: 1 0 1 0 0 1", "5x4: 0 2 0 0 0 0"]
+            .map(|area| TreeArea::try_from(area).unwrap());
+        for area in tree_areas[..2].iter().chain(&extra) {
+            // Every single present has to be placed once, every cell can be used at most once
+            let needed = area.needed(&shapes).unwrap();
+            let mut builder = ExactCover::builder();
+            let presents = needed
+                .iter()
+                .map(|(_, count)| builder.primary_columns(*count))
+                .collect::<Vec<_>>();
+            let cells = builder.secondary_columns(area.area.0 * area.area.1);
+            for (shape, covered) in &Packer::new(area.area, &needed).placements {
+                for present in presents[*shape].clone() {
+                    let covered = covered.iter().map(|pos| cells.start + pos);
+                    builder.add_row(std::iter::once(present).chain(covered));
+                }
+            }
+
+            let exact_cover = builder.build().first_solution();
+            let packing = area.pack(&shapes).unwrap();
+            assert_eq!(exact_cover.is_some(), packing.is_some(), "{area:?}");
+        }
+    }
+
+    #[test]
+    fn orientations() {
+        let (shapes, _) = parse_input(INPUT).unwrap();
+        let counts = shapes
+            .iter()
+            .map(|shape| shape.orientations().len())
+            .collect::<Vec<_>>();
+        assert_eq!(counts, vec![8, 8, 2, 4, 4, 2]);
+    }
+
+    #[test]
+    fn placement_is_valid() {
+        let (shapes, tree_areas) = parse_input(INPUT).unwrap();
+        let area = &tree_areas[1];
+        let placement = area.pack(&shapes).unwrap().unwrap();
+
+        let mut covered = HashSet::new();
+        for piece in &placement.pieces {
+            for (r, c) in &piece.cells {
+                assert!(*r < area.area.1 && *c < area.area.0);
+                assert!(covered.insert((*r, *c)), "Presents overlap");
+            }
+        }
+        assert_eq!(placement.pieces.len(), 6);
     }
 }
@@ -1,5 +1,6 @@
 use crate::Day;
 use anyhow::Result;
+use aoc_common::interval_set::IntervalSet;
 use std::path::Path;
 
 pub(crate) struct DayTwo {
@@ -18,13 +19,10 @@ impl Day for DayTwo {
 
     fn part_one(&self) -> Result<()> {
         let invalid_sum = parse_input(&self.input)?
-            .into_iter()
-            .map(|r| {
-                r.into_iter()
-                    .filter(|i| !i.valid())
-                    .map(|i| i.0)
-                    .sum::<usize>()
-            })
+            .values()
+            .map(Id)
+            .filter(|i| !i.valid())
+            .map(|i| i.0)
             .sum::<usize>();
         println!("Day 2 - Part 1: Sum of invalid IDs: {invalid_sum}");
         Ok(())
@@ -32,56 +30,16 @@ impl Day for DayTwo {
 
     fn part_two(&self) -> Result<()> {
         let invalid_sum = parse_input(&self.input)?
-            .into_iter()
-            .map(|r| {
-                r.into_iter()
-                    .filter(|i| !i.valid_complex())
-                    .map(|i| i.0)
-                    .sum::<usize>()
-            })
+            .values()
+            .map(Id)
+            .filter(|i| !i.valid_complex())
+            .map(|i| i.0)
             .sum::<usize>();
         println!("Day 2 - Part 2: Sum of complex invalid IDs: {invalid_sum}");
         Ok(())
     }
 }
 
-#[derive(Debug)]
-struct IdRange {
-    start: Id,
-    end: Id,
-}
-
-impl IntoIterator for IdRange {
-    type Item = Id;
-    type IntoIter = IdRangeIter;
-
-    fn into_iter(self) -> Self::IntoIter {
-        IdRangeIter {
-            curr: self.start,
-            end: self.end.next(),
-        }
-    }
-}
-
-struct IdRangeIter {
-    curr: Id,
-    end: Id,
-}
-
-impl Iterator for IdRangeIter {
-    type Item = Id;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.curr == self.end {
-            return None;
-        }
-
-        let yielded = self.curr;
-        self.curr = self.curr.next();
-        Some(yielded)
-    }
-}
-
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct Id(usize);
 
@@ -124,10 +82,6 @@ impl Id {
 
         true
     }
-
-    fn next(self) -> Id {
-        Id(self.0 + 1)
-    }
 }
 
 impl<N> From<N> for Id
@@ -139,7 +93,7 @@ where
     }
 }
 
-fn parse_input(input: &str) -> Result<Vec<IdRange>> {
+fn parse_input(input: &str) -> Result<IntervalSet<usize>> {
     input
         .split(",")
         .map(|s| {
@@ -149,12 +103,9 @@ fn parse_input(input: &str) -> Result<Vec<IdRange>> {
                 .ok_or(anyhow::Error::msg("Empty ID range"))?
                 .split_once("-")
                 .ok_or(anyhow::Error::msg("Malformed range"))?;
-            Ok(IdRange {
-                start: Id(a.parse()?),
-                end: Id(b.parse()?),
-            })
+            Ok(a.parse::<usize>()?..=b.parse::<usize>()?)
         })
-        .collect::<Result<Vec<_>>>()
+        .collect::<Result<IntervalSet<_>>>()
 }
 
 #[cfg(test)]
@@ -167,13 +118,10 @@ mod test {
     fn part_one() {
         let invalid_sum = parse_input(INPUT)
             .unwrap()
-            .into_iter()
-            .map(|r| {
-                r.into_iter()
-                    .filter(|i| !i.valid())
-                    .map(|i| i.0)
-                    .sum::<usize>()
-            })
+            .values()
+            .map(Id)
+            .filter(|i| !i.valid())
+            .map(|i| i.0)
             .sum::<usize>();
         assert_eq!(invalid_sum, 1227775554);
     }
@@ -182,13 +130,10 @@ mod test {
     fn part_two() {
         let invalid_complex_sum = parse_input(INPUT)
             .unwrap()
-            .into_iter()
-            .map(|r| {
-                r.into_iter()
-                    .filter(|i| !i.valid_complex())
-                    .map(|i| i.0)
-                    .sum::<usize>()
-            })
+            .values()
+            .map(Id)
+            .filter(|i| !i.valid_complex())
+            .map(|i| i.0)
             .sum::<usize>();
         assert_eq!(invalid_complex_sum, 4174379265);
     }
@@ -1,5 +1,6 @@
 use crate::Day;
-use anyhow::Result;
+use anyhow::{Context, Result};
+use aoc_common::interval_set::IntervalSet;
 use std::path::Path;
 
 pub(crate) struct DayFive {
@@ -25,14 +26,16 @@ impl Day for DayFive {
 
     fn part_two(&self) -> Result<()> {
         let ingredients = Ingredients::try_from(self.input.as_str()).unwrap();
-        let fresh = ingredients.max_allowed_fresh_ingredients();
+        let fresh = ingredients
+            .max_allowed_fresh_ingredients()
+            .context("Too many fresh ingredients to count")?;
         println!("Day 5 - Part 2: Max number of fresh ingredients: {fresh}");
         Ok(())
     }
 }
 
 struct Ingredients {
-    fresh_ranges: Vec<(usize, usize)>,
+    fresh_ranges: IntervalSet<usize>,
     ingredients: Vec<usize>,
 }
 
@@ -44,24 +47,12 @@ impl Ingredients {
             .count()
     }
 
-    fn max_allowed_fresh_ingredients(&self) -> usize {
-        self.fresh_ranges
-            .iter()
-            .map(|(start, end)| end - start + 1)
-            .sum()
+    fn max_allowed_fresh_ingredients(&self) -> Option<usize> {
+        self.fresh_ranges.len()
     }
 
     fn is_fresh(&self, ingredient: usize) -> bool {
-        // It is guaranteed on construction that the ranges are sorted.
-        // Therefore we can stop early if the number is lower than the start of the next range.
-        for (start, end) in self.fresh_ranges.iter() {
-            if ingredient < *start {
-                return false;
-            } else if ingredient <= *end {
-                return true;
-            }
-        }
-        false
+        self.fresh_ranges.contains(ingredient)
     }
 }
 
@@ -71,31 +62,14 @@ impl TryFrom<&str> for Ingredients {
     fn try_from(input: &str) -> Result<Self, Self::Error> {
         let (fresh_ranges, ingredients) = input.split_once("\n\n").ok_or(anyhow::Error::msg(""))?;
 
-        let mut fresh_ranges = fresh_ranges
+        // Overlapping ranges are merged by the interval set on construction
+        let fresh_ranges = fresh_ranges
             .lines()
             .map(|line| line.split_once('-').ok_or(anyhow::Error::msg("")))
             .map(|res| {
-                res.and_then(|(start, end)| Ok((start.parse::<usize>()?, end.parse::<usize>()?)))
+                res.and_then(|(start, end)| Ok(start.parse::<usize>()?..=end.parse::<usize>()?))
             })
-            .collect::<Result<Vec<_>>>()?;
-
-        fresh_ranges.sort_unstable_by_key(|(start, _end)| *start);
-
-        let mut merged_ranges = Vec::with_capacity(fresh_ranges.len());
-        merged_ranges.push(*fresh_ranges.first().ok_or(anyhow::Error::msg(""))?);
-
-        for curr in fresh_ranges.into_iter().skip(1) {
-            let prev = merged_ranges.last_mut().unwrap();
-
-            // Merge if current start is less or equal to end of the previous one
-            //
-            // Its guaranteed on construction that the ranges are sorted by start value
-            if curr.0 <= prev.1 && curr.1 > prev.1 {
-                prev.1 = curr.1;
-            } else if curr.0 > prev.1 {
-                merged_ranges.push(curr);
-            }
-        }
+            .collect::<Result<IntervalSet<_>>>()?;
 
         let ingredients = ingredients
             .lines()
@@ -103,7 +77,7 @@ impl TryFrom<&str> for Ingredients {
             .collect::<Result<Vec<_>>>()?;
 
         Ok(Ingredients {
-            fresh_ranges: merged_ranges,
+            fresh_ranges,
             ingredients,
         })
     }
@@ -137,6 +111,6 @@ mod test {
     fn part_two() {
         let ingredients = Ingredients::try_from(INPUT).unwrap();
         let fresh = ingredients.max_allowed_fresh_ingredients();
-        assert_eq!(fresh, 14);
+        assert_eq!(fresh, Some(14));
     }
 }
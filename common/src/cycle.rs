@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+/// Cycle of a sequence `x0, f(x0), f(f(x0)), ...`: the state at index `start` is the first one
+/// that repeats, and it repeats every `len` steps from then on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Cycle {
+    pub start: usize,
+    pub len: usize,
+}
+
+impl Cycle {
+    /// Smallest index whose state equals the state at index `n`.
+    pub fn reduce(&self, n: usize) -> usize {
+        if n < self.start {
+            n
+        } else {
+            self.start + (n - self.start) % self.len
+        }
+    }
+}
+
+/// State that is compared by its 64 bit hash only, the hash is computed once on creation.
+#[derive(Clone)]
+struct Hashed<T> {
+    hash: u64,
+    state: T,
+}
+
+impl<T: Hash> Hashed<T> {
+    fn new(state: T) -> Self {
+        let mut hasher = DefaultHasher::new();
+        state.hash(&mut hasher);
+        Self {
+            hash: hasher.finish(),
+            state,
+        }
+    }
+}
+
+impl<T> PartialEq for Hashed<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash
+    }
+}
+
+/// Finds the cycle of the sequence created by repeatedly applying `step` to `initial` with
+/// Brent's algorithm, keeping only two states in memory.
+///
+/// States are compared in full, so the result is exact. For big states use [`brent_hashed`].
+///
+/// `step` has to be deterministic and the sequence has to be eventually periodic, otherwise this
+/// never returns.
+pub fn brent<T, F>(initial: T, mut step: F) -> Cycle
+where
+    T: Clone + PartialEq,
+    F: FnMut(&T) -> T,
+{
+    // Search successive powers of two until the hare meets the tortoise, that gives the length
+    let mut power = 1;
+    let mut len = 1;
+    let mut tortoise = initial.clone();
+    let mut hare = step(&initial);
+    while tortoise != hare {
+        if power == len {
+            tortoise = hare.clone();
+            power *= 2;
+            len = 0;
+        }
+        hare = step(&hare);
+        len += 1;
+    }
+
+    // With a head start of `len` steps the hare meets the tortoise right at the cycle start
+    let mut tortoise = initial.clone();
+    let mut hare = initial;
+    for _ in 0..len {
+        hare = step(&hare);
+    }
+    let mut start = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        start += 1;
+    }
+
+    Cycle { start, len }
+}
+
+/// Same as [`brent`] but with Floyd's tortoise and hare which needs more steps.
+pub fn floyd<T, F>(initial: T, mut step: F) -> Cycle
+where
+    T: Clone + PartialEq,
+    F: FnMut(&T) -> T,
+{
+    let mut tortoise = step(&initial);
+    let mut hare = step(&tortoise);
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        let next = step(&hare);
+        hare = step(&next);
+    }
+
+    let mut start = 0;
+    let mut tortoise = initial;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        start += 1;
+    }
+
+    let mut len = 1;
+    hare = step(&tortoise);
+    while tortoise != hare {
+        hare = step(&hare);
+        len += 1;
+    }
+
+    Cycle { start, len }
+}
+
+/// Same as [`brent`] but states are compared by their 64 bit hash, which is computed once per
+/// state instead of comparing whole states on every step.
+///
+/// Two different states with the same hash end the search early with a wrong cycle, which is
+/// unlikely enough to be ignored for the number of states a simulation goes through.
+pub fn brent_hashed<T, F>(initial: T, mut step: F) -> Cycle
+where
+    T: Clone + Hash,
+    F: FnMut(&T) -> T,
+{
+    brent(Hashed::new(initial), |curr| Hashed::new(step(&curr.state)))
+}
+
+/// Same as [`floyd`] but states are compared by their 64 bit hash like for [`brent_hashed`].
+pub fn floyd_hashed<T, F>(initial: T, mut step: F) -> Cycle
+where
+    T: Clone + Hash,
+    F: FnMut(&T) -> T,
+{
+    floyd(Hashed::new(initial), |curr| Hashed::new(step(&curr.state)))
+}
+
+/// Finds the first state of `states` that was already seen before by remembering the index of
+/// every state, `None` if the sequence ends without any repetition.
+pub fn first_repeat<T, I>(states: I) -> Option<Cycle>
+where
+    T: Hash + Eq,
+    I: IntoIterator<Item = T>,
+{
+    let mut seen = HashMap::new();
+    for (idx, state) in states.into_iter().enumerate() {
+        if let Some(start) = seen.insert(state, idx) {
+            return Some(Cycle {
+                start,
+                len: idx - start,
+            });
+        }
+    }
+    None
+}
+
+/// State after applying `step` `n` times to `initial`, skipping all whole cycles.
+pub fn fast_forward<T, F>(initial: T, mut step: F, n: usize) -> T
+where
+    T: Clone + PartialEq,
+    F: FnMut(&T) -> T,
+{
+    let cycle = brent(initial.clone(), &mut step);
+    (0..cycle.reduce(n)).fold(initial, |state, _| step(&state))
+}
+
+/// Same as [`fast_forward`] but the cycle is found with [`brent_hashed`].
+pub fn fast_forward_hashed<T, F>(initial: T, mut step: F, n: usize) -> T
+where
+    T: Clone + Hash,
+    F: FnMut(&T) -> T,
+{
+    let cycle = brent_hashed(initial.clone(), &mut step);
+    (0..cycle.reduce(n)).fold(initial, |state, _| step(&state))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // 3 -> 10 -> 5 -> 16 -> 8 -> 4 -> 2 -> 1 -> 4 -> ...
+    fn collatz(n: &u64) -> u64 {
+        if n.is_multiple_of(2) {
+            n / 2
+        } else {
+            3 * n + 1
+        }
+    }
+
+    #[test]
+    fn detect_cycle() {
+        let expected = Cycle { start: 5, len: 3 };
+        assert_eq!(brent(3, collatz), expected);
+        assert_eq!(floyd(3, collatz), expected);
+        assert_eq!(brent_hashed(3, collatz), expected);
+        assert_eq!(floyd_hashed(3, collatz), expected);
+        assert_eq!(
+            first_repeat(std::iter::successors(Some(3), |n| Some(collatz(n)))),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn fixed_point() {
+        let step = |n: &u32| n.saturating_sub(1);
+        assert_eq!(brent(4, step), Cycle { start: 4, len: 1 });
+        assert_eq!(floyd(0, step), Cycle { start: 0, len: 1 });
+    }
+
+    #[test]
+    fn no_repeat() {
+        assert_eq!(first_repeat(0..100), None);
+    }
+
+    #[test]
+    fn skip_cycles() {
+        let step = |n: &u64| (n * n + 1) % 1_000_003;
+        let cycle = brent(2, step);
+
+        let n = 1_000_000_000_000;
+        let slow = (0..cycle.reduce(n)).fold(2, |state, _| step(&state));
+        assert_eq!(fast_forward(2, step, n), slow);
+        assert_eq!(fast_forward(3, collatz, 1_000_000_000_000), 1);
+        assert_eq!(cycle.reduce(cycle.start + 5 * cycle.len), cycle.start);
+    }
+
+    #[test]
+    fn hashed_states() {
+        // Rotating a grid row by row repeats after as many steps as it has rows
+        let grid = (0..50).map(|row| vec![row; 100]).collect::<Vec<_>>();
+        let step = |grid: &Vec<Vec<u32>>| {
+            let mut next = grid.clone();
+            next.rotate_left(1);
+            next
+        };
+        assert_eq!(
+            brent_hashed(grid.clone(), step),
+            Cycle { start: 0, len: 50 }
+        );
+        assert_eq!(floyd_hashed(grid.clone(), step), brent(grid.clone(), step));
+        assert_eq!(
+            fast_forward_hashed(grid.clone(), step, 1_000_000_000_007)[0][0],
+            7
+        );
+    }
+}
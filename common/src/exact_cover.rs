@@ -0,0 +1,374 @@
+use std::ops::Range;
+
+/// Exact cover problem solved with Knuth's Algorithm X on dancing links.
+///
+/// A solution is a set of rows that covers every primary column exactly once and every secondary
+/// column at most once. Build one with [`ExactCover::builder`].
+#[derive(Clone, Debug)]
+pub struct ExactCover {
+    /// Node 0 is the root, nodes `1..=columns` are the column headers, all others are row cells
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    /// Column header of every node
+    column: Vec<usize>,
+    /// Row of every cell node
+    row: Vec<usize>,
+    /// Number of rows still in every column, indexed by header node
+    size: Vec<usize>,
+}
+
+/// Collects the columns and rows of an [`ExactCover`] problem.
+#[derive(Clone, Debug, Default)]
+pub struct ExactCoverBuilder {
+    /// Whether each column has to be covered
+    primary: Vec<bool>,
+    rows: Vec<Vec<usize>>,
+}
+
+impl ExactCoverBuilder {
+    /// Adds `count` columns that have to be covered exactly once and returns their ids.
+    pub fn primary_columns(&mut self, count: usize) -> Range<usize> {
+        self.add_columns(count, true)
+    }
+
+    /// Adds `count` columns that may be covered at most once and returns their ids.
+    pub fn secondary_columns(&mut self, count: usize) -> Range<usize> {
+        self.add_columns(count, false)
+    }
+
+    fn add_columns(&mut self, count: usize, primary: bool) -> Range<usize> {
+        let start = self.primary.len();
+        self.primary.extend(std::iter::repeat_n(primary, count));
+        start..self.primary.len()
+    }
+
+    /// Adds a row covering `columns` and returns its id. Panics if a column does not exist.
+    pub fn add_row(&mut self, columns: impl IntoIterator<Item = usize>) -> usize {
+        let mut columns = columns.into_iter().collect::<Vec<_>>();
+        assert!(
+            columns.iter().all(|col| *col < self.primary.len()),
+            "Row covers an unknown column"
+        );
+        columns.sort_unstable();
+        columns.dedup();
+        self.rows.push(columns);
+        self.rows.len() - 1
+    }
+
+    pub fn build(self) -> ExactCover {
+        let columns = self.primary.len();
+        let nodes = 1 + columns + self.rows.iter().map(Vec::len).sum::<usize>();
+        let mut dlx = ExactCover {
+            left: Vec::with_capacity(nodes),
+            right: Vec::with_capacity(nodes),
+            up: Vec::with_capacity(nodes),
+            down: Vec::with_capacity(nodes),
+            column: Vec::with_capacity(nodes),
+            row: Vec::with_capacity(nodes),
+            size: vec![0; columns + 1],
+        };
+
+        // Only primary columns are linked into the header list, secondary ones link to themselves
+        // so the search never has to choose them
+        let mut last = 0;
+        dlx.push_node(0, usize::MAX);
+        for (idx, primary) in self.primary.iter().enumerate() {
+            let node = dlx.push_node(idx + 1, usize::MAX);
+            if *primary {
+                dlx.link_horizontal(last, node);
+                last = node;
+            }
+        }
+        dlx.link_horizontal(last, 0);
+
+        for (row, columns) in self.rows.iter().enumerate() {
+            let mut first = None;
+            for col in columns {
+                let header = col + 1;
+                let node = dlx.push_node(header, row);
+                dlx.up[node] = dlx.up[header];
+                dlx.down[node] = header;
+                let above = dlx.up[header];
+                dlx.down[above] = node;
+                dlx.up[header] = node;
+                dlx.size[header] += 1;
+
+                match first {
+                    None => first = Some(node),
+                    Some(first) => {
+                        let prev = dlx.left[first];
+                        dlx.link_horizontal(prev, node);
+                        dlx.link_horizontal(node, first);
+                    }
+                }
+            }
+        }
+
+        dlx
+    }
+}
+
+impl ExactCover {
+    pub fn builder() -> ExactCoverBuilder {
+        ExactCoverBuilder::default()
+    }
+
+    fn push_node(&mut self, column: usize, row: usize) -> usize {
+        let node = self.left.len();
+        self.left.push(node);
+        self.right.push(node);
+        self.up.push(node);
+        self.down.push(node);
+        self.column.push(column);
+        self.row.push(row);
+        node
+    }
+
+    fn link_horizontal(&mut self, a: usize, b: usize) {
+        self.right[a] = b;
+        self.left[b] = a;
+    }
+
+    /// Removes column `c` and every row that intersects it.
+    fn cover(&mut self, c: usize) {
+        let (l, r) = (self.left[c], self.right[c]);
+        self.right[l] = r;
+        self.left[r] = l;
+
+        let mut i = self.down[c];
+        while i != c {
+            let mut j = self.right[i];
+            while j != i {
+                let (u, d) = (self.up[j], self.down[j]);
+                self.down[u] = d;
+                self.up[d] = u;
+                self.size[self.column[j]] -= 1;
+                j = self.right[j];
+            }
+            i = self.down[i];
+        }
+    }
+
+    /// Exact inverse of [`ExactCover::cover`], the dancing part of dancing links.
+    fn uncover(&mut self, c: usize) {
+        let mut i = self.up[c];
+        while i != c {
+            let mut j = self.left[i];
+            while j != i {
+                self.size[self.column[j]] += 1;
+                let (u, d) = (self.up[j], self.down[j]);
+                self.down[u] = j;
+                self.up[d] = j;
+                j = self.left[j];
+            }
+            i = self.up[i];
+        }
+
+        let (l, r) = (self.left[c], self.right[c]);
+        self.right[l] = c;
+        self.left[r] = c;
+    }
+
+    /// Covers the other columns of the row of node `r` after its own column was covered.
+    fn select(&mut self, r: usize) {
+        let mut j = self.right[r];
+        while j != r {
+            self.cover(self.column[j]);
+            j = self.right[j];
+        }
+    }
+
+    fn deselect(&mut self, r: usize) {
+        let mut j = self.left[r];
+        while j != r {
+            self.uncover(self.column[j]);
+            j = self.left[j];
+        }
+    }
+
+    /// Lazily enumerates all solutions as sorted row ids.
+    pub fn solutions(&self) -> Solutions {
+        Solutions {
+            dlx: self.clone(),
+            chosen: Vec::new(),
+            state: SearchState::Descend,
+        }
+    }
+
+    pub fn first_solution(&self) -> Option<Vec<usize>> {
+        self.solutions().next()
+    }
+
+    pub fn count_solutions(&self) -> usize {
+        self.solutions().count()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum SearchState {
+    /// Choose a column on the next level
+    Descend,
+    /// Undo the last chosen row and try the next one in its column
+    Backtrack,
+    Done,
+}
+
+/// Iterator over the solutions of an [`ExactCover`] problem, see [`ExactCover::solutions`].
+///
+/// The recursion of Algorithm X is unrolled into an explicit stack of chosen row nodes so the
+/// search can pause after every solution.
+#[derive(Clone, Debug)]
+pub struct Solutions {
+    dlx: ExactCover,
+    chosen: Vec<usize>,
+    state: SearchState,
+}
+
+impl Solutions {
+    /// Selects the row of node `r`, returns whether there was one. If `r` is the column header
+    /// instead, every row of the column was tried and the column is uncovered again.
+    fn try_from_row(&mut self, r: usize) -> bool {
+        let dlx = &mut self.dlx;
+        let c = dlx.column[r];
+        if r == c {
+            dlx.uncover(c);
+            return false;
+        }
+        dlx.select(r);
+        self.chosen.push(r);
+        true
+    }
+
+    fn current_solution(&self) -> Vec<usize> {
+        let mut rows = self
+            .chosen
+            .iter()
+            .map(|node| self.dlx.row[*node])
+            .collect::<Vec<_>>();
+        rows.sort_unstable();
+        rows
+    }
+}
+
+impl Iterator for Solutions {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Vec<usize>> {
+        loop {
+            match self.state {
+                SearchState::Done => return None,
+                SearchState::Descend => {
+                    let dlx = &mut self.dlx;
+                    if dlx.right[0] == 0 {
+                        self.state = SearchState::Backtrack;
+                        return Some(self.current_solution());
+                    }
+
+                    // Always branch on the column with the fewest remaining rows
+                    let mut c = dlx.right[0];
+                    let mut j = dlx.right[c];
+                    while j != 0 {
+                        if dlx.size[j] < dlx.size[c] {
+                            c = j;
+                        }
+                        j = dlx.right[j];
+                    }
+
+                    dlx.cover(c);
+                    let first = dlx.down[c];
+                    if !self.try_from_row(first) {
+                        self.state = SearchState::Backtrack;
+                    }
+                }
+                SearchState::Backtrack => {
+                    let Some(r) = self.chosen.pop() else {
+                        self.state = SearchState::Done;
+                        continue;
+                    };
+                    self.dlx.deselect(r);
+                    let next = self.dlx.down[r];
+                    if self.try_from_row(next) {
+                        self.state = SearchState::Descend;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn knuth_example() {
+        let mut builder = ExactCover::builder();
+        let [a, b, c, d, e, f, g] = builder.primary_columns(7).collect::<Vec<_>>()[..] else {
+            unreachable!()
+        };
+        builder.add_row([c, e, f]);
+        builder.add_row([a, d, g]);
+        builder.add_row([b, c, f]);
+        builder.add_row([a, d]);
+        builder.add_row([b, g]);
+        builder.add_row([d, e, g]);
+
+        let problem = builder.build();
+        assert_eq!(problem.first_solution(), Some(vec![0, 3, 4]));
+        assert_eq!(problem.count_solutions(), 1);
+    }
+
+    #[test]
+    fn no_solution() {
+        let mut builder = ExactCover::builder();
+        let cols = builder.primary_columns(3);
+        builder.add_row([cols.start, cols.start + 1]);
+        builder.add_row([cols.start + 1, cols.start + 2]);
+        assert_eq!(builder.build().first_solution(), None);
+
+        // Without any primary column the empty selection is the only solution
+        let mut builder = ExactCover::builder();
+        let optional = builder.secondary_columns(1);
+        builder.add_row(optional);
+        assert_eq!(
+            builder.build().solutions().collect::<Vec<_>>(),
+            vec![Vec::<usize>::new()]
+        );
+    }
+
+    fn queens(n: usize) -> ExactCover {
+        // Every row and column needs a queen, the diagonals can have at most one
+        let mut builder = ExactCover::builder();
+        let ranks = builder.primary_columns(n);
+        let files = builder.primary_columns(n);
+        let diagonals = builder.secondary_columns(2 * n - 1);
+        let anti_diagonals = builder.secondary_columns(2 * n - 1);
+        for r in 0..n {
+            for f in 0..n {
+                builder.add_row([
+                    ranks.start + r,
+                    files.start + f,
+                    diagonals.start + r + f,
+                    anti_diagonals.start + r + n - 1 - f,
+                ]);
+            }
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn n_queens() {
+        assert_eq!(queens(4).count_solutions(), 2);
+        assert_eq!(queens(6).count_solutions(), 4);
+        assert_eq!(queens(8).count_solutions(), 92);
+
+        let problem = queens(8);
+        let all = problem.solutions().collect::<Vec<_>>();
+        assert_eq!(problem.first_solution().as_ref(), all.first());
+        for solution in all {
+            assert_eq!(solution.len(), 8);
+        }
+    }
+}
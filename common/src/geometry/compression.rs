@@ -0,0 +1,79 @@
+/// Coordinate compression of one axis.
+///
+/// Every distinct coordinate gets its own band, and the values strictly between two neighbouring
+/// coordinates are merged into a single band. Anything that only changes at the given coordinates
+/// is therefore constant within a band.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompressedAxis {
+    bands: Vec<(i64, i64)>,
+}
+
+impl CompressedAxis {
+    pub fn new(coords: impl IntoIterator<Item = i64>) -> Self {
+        let mut coords = coords.into_iter().collect::<Vec<_>>();
+        coords.sort_unstable();
+        coords.dedup();
+
+        let mut bands = Vec::with_capacity(2 * coords.len());
+        for (idx, coord) in coords.iter().enumerate() {
+            if let Some(prev) = idx.checked_sub(1).map(|prev| coords[prev]) {
+                if coord - prev > 1 {
+                    bands.push((prev + 1, coord - 1));
+                }
+            }
+            bands.push((*coord, *coord));
+        }
+        Self { bands }
+    }
+
+    /// Number of bands.
+    pub fn len(&self) -> usize {
+        self.bands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bands.is_empty()
+    }
+
+    /// Inclusive range of values covered by band `idx`.
+    pub fn band(&self, idx: usize) -> (i64, i64) {
+        self.bands[idx]
+    }
+
+    /// Number of values covered by band `idx`.
+    pub fn band_len(&self, idx: usize) -> u64 {
+        let (start, end) = self.bands[idx];
+        start.abs_diff(end) + 1
+    }
+
+    /// Band containing `value`, `None` if it lies outside the compressed coordinates.
+    pub fn index_of(&self, value: i64) -> Option<usize> {
+        let idx = self.bands.partition_point(|(_, end)| *end < value);
+        self.bands
+            .get(idx)
+            .is_some_and(|(start, _)| *start <= value)
+            .then_some(idx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bands() {
+        let axis = CompressedAxis::new([7, 2, 3, 7, 11]);
+        assert_eq!(axis.len(), 6);
+        assert_eq!(
+            (0..axis.len())
+                .map(|idx| axis.band(idx))
+                .collect::<Vec<_>>(),
+            vec![(2, 2), (3, 3), (4, 6), (7, 7), (8, 10), (11, 11)]
+        );
+        assert_eq!(axis.band_len(2), 3);
+        assert_eq!(axis.index_of(5), Some(2));
+        assert_eq!(axis.index_of(11), Some(5));
+        assert_eq!(axis.index_of(1), None);
+        assert_eq!(axis.index_of(12), None);
+    }
+}
@@ -0,0 +1,240 @@
+use super::Vec3;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Number of neighbours fetched per point when a [`ClosestPairs`] stream starts, doubled whenever
+/// a point runs out of them.
+const INITIAL_BATCH: usize = 4;
+
+/// Static k-d tree over 3D points for nearest neighbour queries by squared euclidean distance.
+///
+/// Points are identified by their index in the slice the tree was built from. The tree is stored
+/// implicitly: the median of every slice is its root, splitting along x, y and z in turn.
+#[derive(Clone, Debug)]
+pub struct KdTree {
+    points: Vec<Vec3>,
+    nodes: Vec<(Vec3, usize)>,
+}
+
+impl KdTree {
+    pub fn new(points: &[Vec3]) -> Self {
+        let mut nodes = points.iter().copied().zip(0..).collect::<Vec<_>>();
+        Self::build(&mut nodes, 0);
+        Self {
+            points: points.to_vec(),
+            nodes,
+        }
+    }
+
+    fn build(nodes: &mut [(Vec3, usize)], depth: usize) {
+        if nodes.len() <= 1 {
+            return;
+        }
+        let mid = nodes.len() / 2;
+        nodes.select_nth_unstable_by_key(mid, |(p, _)| p.to_array()[depth % 3]);
+        let (left, right) = nodes.split_at_mut(mid);
+        Self::build(left, depth + 1);
+        Self::build(&mut right[1..], depth + 1);
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn point(&self, idx: usize) -> Vec3 {
+        self.points[idx]
+    }
+
+    /// Closest point to `query` with its squared distance.
+    pub fn nearest(&self, query: Vec3) -> Option<(usize, i64)> {
+        self.k_nearest(query, 1).into_iter().next()
+    }
+
+    /// The `k` closest points to `query` with their squared distances, nearest first. Ties are
+    /// broken by the lower index.
+    pub fn k_nearest(&self, query: Vec3, k: usize) -> Vec<(usize, i64)> {
+        self.k_nearest_by(query, k, |_| true)
+    }
+
+    /// Same as [`KdTree::k_nearest`] but only considers the points whose index passes `filter`.
+    pub fn k_nearest_by(
+        &self,
+        query: Vec3,
+        k: usize,
+        filter: impl Fn(usize) -> bool,
+    ) -> Vec<(usize, i64)> {
+        let mut best = BinaryHeap::with_capacity(k + 1);
+        if k > 0 {
+            Self::search(&self.nodes, 0, query, k, &filter, &mut best);
+        }
+        best.into_sorted_vec()
+            .into_iter()
+            .map(|(dist, idx)| (idx, dist))
+            .collect()
+    }
+
+    fn search(
+        nodes: &[(Vec3, usize)],
+        depth: usize,
+        query: Vec3,
+        k: usize,
+        filter: &impl Fn(usize) -> bool,
+        best: &mut BinaryHeap<(i64, usize)>,
+    ) {
+        if nodes.is_empty() {
+            return;
+        }
+        let mid = nodes.len() / 2;
+        let (point, idx) = nodes[mid];
+
+        if filter(idx) {
+            best.push((point.dist_squared(query), idx));
+            if best.len() > k {
+                best.pop();
+            }
+        }
+
+        // Descend into the side of the query first, the other side can only hold closer points
+        // if the splitting plane is closer than the worst point found so far
+        let axis = depth % 3;
+        let offset = query.to_array()[axis] - point.to_array()[axis];
+        let (near, far) = if offset < 0 {
+            (&nodes[..mid], &nodes[mid + 1..])
+        } else {
+            (&nodes[mid + 1..], &nodes[..mid])
+        };
+        Self::search(near, depth + 1, query, k, filter, best);
+        if best.len() < k
+            || best
+                .peek()
+                .is_some_and(|(worst, _)| offset * offset <= *worst)
+        {
+            Self::search(far, depth + 1, query, k, filter, best);
+        }
+    }
+
+    /// All pairs of distinct points ordered by their squared distance, generated lazily.
+    pub fn closest_pairs(&self) -> ClosestPairs<'_> {
+        let mut pairs = ClosestPairs {
+            tree: self,
+            neighbours: vec![Vec::new(); self.len()],
+            requested: vec![0; self.len()],
+            heap: BinaryHeap::with_capacity(self.len()),
+        };
+        for a in 0..self.len() {
+            pairs.push_neighbour(a, 0);
+        }
+        pairs
+    }
+}
+
+/// Iterator over `(a, b, dist_squared)` with `a < b` for all pairs of points of a [`KdTree`],
+/// nearest pairs first and ties broken by the lower indices.
+///
+/// Every point only pairs with the points of a higher index. Its neighbours are fetched from the
+/// tree in batches of doubling size, and a heap holds the next unseen neighbour of every point,
+/// so only the pairs up to the current distance are ever materialized.
+pub struct ClosestPairs<'a> {
+    tree: &'a KdTree,
+    /// Neighbours with a higher index fetched so far for every point, nearest first.
+    neighbours: Vec<Vec<(usize, i64)>>,
+    /// Batch size of the last fetch, fewer neighbours than that means there are no more.
+    requested: Vec<usize>,
+    /// `(dist_squared, a, b, rank)` where `b` is the `rank`-th neighbour of `a`.
+    heap: BinaryHeap<Reverse<(i64, usize, usize, usize)>>,
+}
+
+impl ClosestPairs<'_> {
+    fn push_neighbour(&mut self, a: usize, rank: usize) {
+        let fetched = self.neighbours[a].len();
+        if rank >= fetched && fetched == self.requested[a] {
+            let k = (2 * fetched).max(INITIAL_BATCH);
+            self.neighbours[a] = self
+                .tree
+                .k_nearest_by(self.tree.point(a), k, |other| other > a);
+            self.requested[a] = k;
+        }
+        if let Some((b, dist)) = self.neighbours[a].get(rank) {
+            self.heap.push(Reverse((*dist, a, *b, rank)));
+        }
+    }
+}
+
+impl Iterator for ClosestPairs<'_> {
+    type Item = (usize, usize, i64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((dist, a, b, rank)) = self.heap.pop()?;
+        self.push_neighbour(a, rank + 1);
+        Some((a, b, dist))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn points() -> Vec<Vec3> {
+        // Deterministic pseudo random points with plenty of duplicate coordinates
+        let mut state = 12345_i64;
+        let mut next = move || {
+            state = (state * 1103515245 + 12345).rem_euclid(1 << 31);
+            state % 50
+        };
+        (0..300)
+            .map(|_| Vec3::new(next(), next(), next()))
+            .collect()
+    }
+
+    #[test]
+    fn nearest_neighbours() {
+        let points = points();
+        let tree = KdTree::new(&points);
+
+        for query in [
+            Vec3::new(0, 0, 0),
+            Vec3::new(25, 13, 40),
+            Vec3::new(-5, 60, 7),
+        ] {
+            let mut expected = points
+                .iter()
+                .enumerate()
+                .map(|(idx, p)| (p.dist_squared(query), idx))
+                .collect::<Vec<_>>();
+            expected.sort();
+            let expected = expected
+                .into_iter()
+                .map(|(dist, idx)| (idx, dist))
+                .collect::<Vec<_>>();
+
+            assert_eq!(tree.nearest(query), Some(expected[0]));
+            assert_eq!(tree.k_nearest(query, 10), expected[..10]);
+            assert_eq!(tree.k_nearest(query, 1000), expected);
+        }
+        assert_eq!(KdTree::new(&[]).nearest(Vec3::ZERO), None);
+    }
+
+    #[test]
+    fn pairs_in_order() {
+        let points = points();
+        let tree = KdTree::new(&points);
+
+        let mut expected = Vec::new();
+        for a in 0..points.len() {
+            for b in a + 1..points.len() {
+                expected.push((points[a].dist_squared(points[b]), a, b));
+            }
+        }
+        expected.sort();
+        let expected = expected
+            .into_iter()
+            .map(|(dist, a, b)| (a, b, dist))
+            .collect::<Vec<_>>();
+
+        assert_eq!(tree.closest_pairs().collect::<Vec<_>>(), expected);
+    }
+}
@@ -0,0 +1,11 @@
+mod compression;
+mod kd_tree;
+mod rectilinear;
+mod rotation;
+mod vec3;
+
+pub use compression::CompressedAxis;
+pub use kd_tree::{ClosestPairs, KdTree};
+pub use rectilinear::{ContainmentIndex, RectilinearPolygon};
+pub use rotation::{Rotation, Transform};
+pub use vec3::Vec3;
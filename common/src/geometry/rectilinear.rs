@@ -0,0 +1,252 @@
+use super::CompressedAxis;
+
+type Point = (i64, i64);
+
+/// Simple closed polygon whose edges are all horizontal or vertical, with its vertices on the
+/// integer lattice.
+///
+/// Queries about points treat the boundary as part of the polygon, so a lattice point is
+/// contained if it lies inside or on an edge.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RectilinearPolygon {
+    vertices: Vec<Point>,
+}
+
+impl RectilinearPolygon {
+    /// Polygon through `vertices` in order, closing the loop back to the first vertex. `None` if
+    /// two consecutive vertices are not on a horizontal or vertical line.
+    pub fn new(vertices: Vec<Point>) -> Option<Self> {
+        let polygon = Self { vertices };
+        let axis_aligned = polygon.edges().all(|(a, b)| a.0 == b.0 || a.1 == b.1);
+        axis_aligned.then_some(polygon)
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    /// All edges including the closing one from the last back to the first vertex.
+    pub fn edges(&self) -> impl Iterator<Item = (Point, Point)> + '_ {
+        let second = self.vertices.iter().skip(1).chain(self.vertices.first());
+        self.vertices.iter().copied().zip(second.copied())
+    }
+
+    /// Enclosed area by the shoelace formula.
+    pub fn area(&self) -> i64 {
+        let twice = self
+            .edges()
+            .map(|(a, b)| a.0 * b.1 - b.0 * a.1)
+            .sum::<i64>();
+        twice.abs() / 2
+    }
+
+    /// Number of lattice points on the boundary.
+    pub fn boundary_points(&self) -> i64 {
+        self.edges()
+            .map(|(a, b)| (a.0.abs_diff(b.0) + a.1.abs_diff(b.1)) as i64)
+            .sum()
+    }
+
+    /// Number of lattice points strictly inside, by Pick's theorem.
+    pub fn interior_points(&self) -> i64 {
+        self.area() - self.boundary_points() / 2 + 1
+    }
+
+    /// Number of lattice points inside or on the boundary.
+    pub fn lattice_points(&self) -> i64 {
+        self.interior_points() + self.boundary_points()
+    }
+
+    pub fn on_boundary(&self, point: Point) -> bool {
+        self.edges().any(|(a, b)| {
+            (a.0.min(b.0)..=a.0.max(b.0)).contains(&point.0)
+                && (a.1.min(b.1)..=a.1.max(b.1)).contains(&point.1)
+        })
+    }
+
+    /// Whether `point` lies inside or on the boundary.
+    pub fn contains(&self, point: Point) -> bool {
+        if self.on_boundary(point) {
+            return true;
+        }
+
+        // Cast a ray to the right, only vertical edges can be crossed. The half open range makes
+        // a ray through a vertex count only once.
+        let crossings = self
+            .edges()
+            .filter(|(a, b)| a.0 == b.0 && a.0 > point.0)
+            .filter(|(a, b)| (a.1.min(b.1)..a.1.max(b.1)).contains(&point.1))
+            .count();
+        crossings % 2 == 1
+    }
+
+    /// Precomputes which lattice points are contained, so [`ContainmentIndex::contains_rect`]
+    /// answers in `O(log n)` for a polygon with `n` vertices.
+    pub fn containment_index(&self) -> ContainmentIndex {
+        ContainmentIndex::new(self)
+    }
+}
+
+/// Contained lattice points of a [`RectilinearPolygon`] on its compressed grid.
+///
+/// Compressing both axes at the vertex coordinates gives a grid of at most `2n x 2n` cells in
+/// which every cell is either completely contained or completely outside. A 2D prefix sum over
+/// the cells outside then tells for any axis-aligned rectangle whether it contains a single
+/// lattice point that is not part of the polygon.
+#[derive(Clone, Debug)]
+pub struct ContainmentIndex {
+    xs: CompressedAxis,
+    ys: CompressedAxis,
+    /// `outside[y][x]` is the number of cells outside the polygon in the cells `..y` x `..x`.
+    outside: Vec<Vec<u32>>,
+}
+
+impl ContainmentIndex {
+    fn new(polygon: &RectilinearPolygon) -> Self {
+        let xs = CompressedAxis::new(polygon.vertices.iter().map(|v| v.0));
+        let ys = CompressedAxis::new(polygon.vertices.iter().map(|v| v.1));
+        let index = |axis: &CompressedAxis, v| axis.index_of(v).expect("Vertex was compressed");
+
+        // Boundary cells first, every edge runs between two compressed coordinates
+        let mut contained = vec![vec![false; xs.len()]; ys.len()];
+        for (a, b) in polygon.edges() {
+            let (x0, x1) = (index(&xs, a.0.min(b.0)), index(&xs, a.0.max(b.0)));
+            let (y0, y1) = (index(&ys, a.1.min(b.1)), index(&ys, a.1.max(b.1)));
+            for row in &mut contained[y0..=y1] {
+                row[x0..=x1].fill(true);
+            }
+        }
+
+        // Then sweep every row from left to right, the parity of the vertical edges passed so far
+        // tells whether the cells in between are inside
+        for (y, row) in contained.iter_mut().enumerate() {
+            let rep = ys.band(y).0;
+            let mut crossings = polygon
+                .edges()
+                .filter(|(a, b)| a.0 == b.0)
+                .filter(|(a, b)| (a.1.min(b.1)..a.1.max(b.1)).contains(&rep))
+                .map(|(a, _)| index(&xs, a.0))
+                .collect::<Vec<_>>();
+            crossings.sort_unstable();
+
+            let mut passed = 0;
+            for (x, cell) in row.iter_mut().enumerate() {
+                while passed < crossings.len() && crossings[passed] < x {
+                    passed += 1;
+                }
+                *cell |= passed % 2 == 1;
+            }
+        }
+
+        let mut outside = vec![vec![0; xs.len() + 1]; ys.len() + 1];
+        for y in 0..ys.len() {
+            for x in 0..xs.len() {
+                outside[y + 1][x + 1] = outside[y][x + 1] + outside[y + 1][x] - outside[y][x]
+                    + u32::from(!contained[y][x]);
+            }
+        }
+
+        Self { xs, ys, outside }
+    }
+
+    /// Whether every lattice point of the rectangle spanned by the opposite corners `a` and `b`
+    /// is contained in the polygon.
+    pub fn contains_rect(&self, a: Point, b: Point) -> bool {
+        let cells = (
+            self.xs.index_of(a.0.min(b.0)),
+            self.xs.index_of(a.0.max(b.0)),
+            self.ys.index_of(a.1.min(b.1)),
+            self.ys.index_of(a.1.max(b.1)),
+        );
+        let (Some(x0), Some(x1), Some(y0), Some(y1)) = cells else {
+            return false;
+        };
+
+        let outside = self.outside[y1 + 1][x1 + 1] + self.outside[y0][x0]
+            - self.outside[y0][x1 + 1]
+            - self.outside[y1 + 1][x0];
+        outside == 0
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        self.contains_rect(point, point)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sample() -> RectilinearPolygon {
+        RectilinearPolygon::new(vec![
+            (7, 1),
+            (11, 1),
+            (11, 7),
+            (9, 7),
+            (9, 5),
+            (2, 5),
+            (2, 3),
+            (7, 3),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn lattice_counts() {
+        let polygon = sample();
+        assert_eq!(polygon.area(), 30);
+        assert_eq!(polygon.boundary_points(), 30);
+        assert_eq!(polygon.interior_points(), 16);
+
+        let contained = (0..13)
+            .flat_map(|x| (0..9).map(move |y| (x, y)))
+            .filter(|p| polygon.contains(*p))
+            .count();
+        assert_eq!(polygon.lattice_points(), contained as i64);
+    }
+
+    #[test]
+    fn diagonal_edge() {
+        assert!(RectilinearPolygon::new(vec![(0, 0), (2, 0), (0, 2)]).is_none());
+    }
+
+    #[test]
+    fn rectangles() {
+        let polygon = sample();
+        let index = polygon.containment_index();
+        assert!(index.contains_rect((9, 5), (2, 3)));
+        assert!(!index.contains_rect((11, 1), (2, 5)));
+        assert!(!index.contains_rect((0, 0), (1, 1)));
+
+        // Every rectangle agrees with checking each of its lattice points on its own
+        for (a, b) in polygon
+            .vertices()
+            .iter()
+            .flat_map(|a| polygon.vertices().iter().map(move |b| (*a, *b)))
+        {
+            let expected = (a.0.min(b.0)..=a.0.max(b.0))
+                .flat_map(|x| (a.1.min(b.1)..=a.1.max(b.1)).map(move |y| (x, y)))
+                .all(|p| polygon.contains(p));
+            assert_eq!(index.contains_rect(a, b), expected, "{a:?} {b:?}");
+        }
+    }
+
+    #[test]
+    fn narrow_notch() {
+        // The notch between x = 1 and x = 2 has no lattice points, so the whole square is covered
+        let polygon = RectilinearPolygon::new(vec![
+            (0, 0),
+            (1, 0),
+            (1, 2),
+            (2, 2),
+            (2, 0),
+            (3, 0),
+            (3, 3),
+            (0, 3),
+        ])
+        .unwrap();
+        assert_eq!(polygon.area(), 7);
+        assert_eq!(polygon.lattice_points(), 16);
+        assert!(polygon.containment_index().contains_rect((0, 0), (3, 3)));
+    }
+}
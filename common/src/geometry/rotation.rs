@@ -0,0 +1,161 @@
+use super::Vec3;
+use std::ops::Mul;
+
+/// Proper rotation that maps every axis onto an axis, stored as an integer matrix with exactly
+/// one `1` or `-1` per row and column and a determinant of `1`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rotation {
+    rows: [[i64; 3]; 3],
+}
+
+impl Rotation {
+    pub const IDENTITY: Rotation = Rotation {
+        rows: [[1, 0, 0], [0, 1, 0], [0, 0, 1]],
+    };
+
+    /// All 24 orientations, starting with the identity.
+    ///
+    /// The image of the x axis can be any of the 6 signed axes and the image of the y axis any of
+    /// the 4 signed axes perpendicular to it. The image of the z axis then follows from keeping
+    /// the coordinate system right-handed.
+    pub fn all() -> Vec<Rotation> {
+        let axes = [
+            Vec3::new(1, 0, 0),
+            Vec3::new(0, 1, 0),
+            Vec3::new(0, 0, 1),
+            Vec3::new(-1, 0, 0),
+            Vec3::new(0, -1, 0),
+            Vec3::new(0, 0, -1),
+        ];
+
+        let mut rotations = Vec::with_capacity(24);
+        for x in axes {
+            for y in axes.iter().filter(|y| y.dot(x) == 0) {
+                let z = x.cross(*y);
+                // The images of the axes are the columns of the matrix
+                let rows = [0, 1, 2].map(|r| [x, *y, z].map(|col| col.to_array()[r]));
+                rotations.push(Rotation { rows });
+            }
+        }
+        rotations
+    }
+
+    pub fn apply(&self, v: Vec3) -> Vec3 {
+        Vec3::from(self.rows.map(|row| Vec3::from(row).dot(v)))
+    }
+
+    /// Rotation undoing this one, which for an orthogonal matrix is its transpose.
+    pub fn inverse(&self) -> Rotation {
+        Rotation {
+            rows: [0, 1, 2].map(|r| [0, 1, 2].map(|c| self.rows[c][r])),
+        }
+    }
+
+    /// Rotation applying `self` first and `other` afterwards.
+    pub fn then(&self, other: &Rotation) -> Rotation {
+        *other * *self
+    }
+}
+
+impl Mul for Rotation {
+    type Output = Rotation;
+
+    /// Matrix product, so `(a * b).apply(v) == a.apply(b.apply(v))`.
+    fn mul(self, rhs: Rotation) -> Rotation {
+        let cols = rhs.inverse().rows.map(Vec3::from);
+        Rotation {
+            rows: self
+                .rows
+                .map(|row| cols.map(|col| Vec3::from(row).dot(col))),
+        }
+    }
+}
+
+/// Rigid transform of the lattice: a [`Rotation`] followed by a translation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Transform {
+    pub rotation: Rotation,
+    pub translation: Vec3,
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        rotation: Rotation::IDENTITY,
+        translation: Vec3::ZERO,
+    };
+
+    pub fn new(rotation: Rotation, translation: Vec3) -> Self {
+        Self {
+            rotation,
+            translation,
+        }
+    }
+
+    pub fn apply(&self, v: Vec3) -> Vec3 {
+        self.rotation.apply(v) + self.translation
+    }
+
+    pub fn apply_all<'a>(&self, points: impl IntoIterator<Item = &'a Vec3>) -> Vec<Vec3> {
+        points.into_iter().map(|p| self.apply(*p)).collect()
+    }
+
+    pub fn inverse(&self) -> Transform {
+        let rotation = self.rotation.inverse();
+        Transform {
+            rotation,
+            translation: -rotation.apply(self.translation),
+        }
+    }
+
+    /// Transform applying `self` first and `other` afterwards.
+    pub fn then(&self, other: &Transform) -> Transform {
+        Transform {
+            rotation: self.rotation.then(&other.rotation),
+            translation: other.apply(self.translation),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn orientations() {
+        let rotations = Rotation::all();
+        assert_eq!(rotations.len(), 24);
+        assert_eq!(rotations[0], Rotation::IDENTITY);
+
+        // Every rotation gives a distinct image of an asymmetric point
+        let p = Vec3::new(1, 2, 3);
+        let images = rotations.iter().map(|r| r.apply(p)).collect::<HashSet<_>>();
+        assert_eq!(images.len(), 24);
+
+        // Closed under composition and inversion
+        let all = rotations.iter().collect::<HashSet<_>>();
+        for a in &rotations {
+            assert!(all.contains(&a.inverse()));
+            assert_eq!(a.then(&a.inverse()), Rotation::IDENTITY);
+            for b in &rotations {
+                assert!(all.contains(&(*a * *b)));
+            }
+        }
+    }
+
+    #[test]
+    fn composition() {
+        let rotations = Rotation::all();
+        let a = Transform::new(rotations[5], Vec3::new(1, -2, 3));
+        let b = Transform::new(rotations[17], Vec3::new(-4, 0, 7));
+        let p = Vec3::new(5, 6, -7);
+
+        assert_eq!(a.then(&b).apply(p), b.apply(a.apply(p)));
+        assert_eq!(a.inverse().apply(a.apply(p)), p);
+        assert_eq!(a.then(&a.inverse()), Transform::IDENTITY);
+        assert_eq!(
+            b.apply_all(&[p, Vec3::ZERO]),
+            vec![b.apply(p), b.translation]
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use std::fmt::Display;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+/// Point or vector on the 3D integer lattice.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Vec3 {
+    pub x: i64,
+    pub y: i64,
+    pub z: i64,
+}
+
+impl Vec3 {
+    pub const ZERO: Vec3 = Vec3::new(0, 0, 0);
+
+    pub const fn new(x: i64, y: i64, z: i64) -> Self {
+        Self { x, y, z }
+    }
+
+    pub fn to_array(self) -> [i64; 3] {
+        [self.x, self.y, self.z]
+    }
+
+    pub fn dot(self, other: Vec3) -> i64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn cross(self, other: Vec3) -> Vec3 {
+        Vec3::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    /// Sum of the absolute coordinates.
+    pub fn manhattan_norm(self) -> i64 {
+        self.x.abs() + self.y.abs() + self.z.abs()
+    }
+
+    pub fn manhattan(self, other: Vec3) -> i64 {
+        (self - other).manhattan_norm()
+    }
+
+    /// Squared euclidean distance, which orders points the same way as the euclidean distance
+    /// but stays an integer.
+    pub fn dist_squared(self, other: Vec3) -> i64 {
+        let diff = self - other;
+        diff.dot(diff)
+    }
+}
+
+impl From<[i64; 3]> for Vec3 {
+    fn from([x, y, z]: [i64; 3]) -> Self {
+        Self { x, y, z }
+    }
+}
+
+impl Display for Vec3 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{},{}", self.x, self.y, self.z)
+    }
+}
+
+impl Add for Vec3 {
+    type Output = Vec3;
+
+    fn add(self, rhs: Vec3) -> Vec3 {
+        Vec3::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl Sub for Vec3 {
+    type Output = Vec3;
+
+    fn sub(self, rhs: Vec3) -> Vec3 {
+        Vec3::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl Neg for Vec3 {
+    type Output = Vec3;
+
+    fn neg(self) -> Vec3 {
+        Vec3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl Mul<i64> for Vec3 {
+    type Output = Vec3;
+
+    fn mul(self, rhs: i64) -> Vec3 {
+        Vec3::new(self.x * rhs, self.y * rhs, self.z * rhs)
+    }
+}
+
+impl AddAssign for Vec3 {
+    fn add_assign(&mut self, rhs: Vec3) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Vec3 {
+    fn sub_assign(&mut self, rhs: Vec3) {
+        *self = *self - rhs;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn distances() {
+        let a = Vec3::new(1, -2, 3);
+        let b = Vec3::new(-4, 2, 3);
+        assert_eq!(a.manhattan(b), 9);
+        assert_eq!(a.dist_squared(b), 41);
+        assert_eq!(a - b + b, a);
+        assert_eq!(-a * 2, Vec3::new(-2, 4, -6));
+    }
+
+    #[test]
+    fn products() {
+        let x = Vec3::new(1, 0, 0);
+        let y = Vec3::new(0, 1, 0);
+        assert_eq!(x.cross(y), Vec3::new(0, 0, 1));
+        assert_eq!(x.dot(y), 0);
+        assert_eq!(Vec3::from([1, 2, 3]).to_string(), "1,2,3");
+    }
+}
@@ -0,0 +1,208 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt::Display;
+
+/// Directed graph on the nodes `0..len` stored as successor lists.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DirectedGraph {
+    successors: Vec<Vec<usize>>,
+}
+
+/// Result of a topological sort.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TopologicalOrder {
+    /// Every node comes before all of its successors. Among the valid orders this is the
+    /// lexicographically smallest one.
+    pub order: Vec<usize>,
+    /// Whether this is the only valid order, which is the case exactly if every pair of
+    /// neighbouring nodes in it is connected by an edge.
+    pub unique: bool,
+}
+
+/// Error of a topological sort on a graph that contains a cycle.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CycleError {
+    /// Nodes of one cycle starting at its lowest node, every node has an edge to the next one and
+    /// the last to the first.
+    pub nodes: Vec<usize>,
+}
+
+impl Display for CycleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Graph contains the cycle ")?;
+        for node in &self.nodes {
+            write!(f, "{node} -> ")?;
+        }
+        match self.nodes.first() {
+            Some(first) => write!(f, "{first}"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for CycleError {}
+
+impl DirectedGraph {
+    pub fn new(len: usize) -> Self {
+        Self {
+            successors: vec![Vec::new(); len],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.successors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.successors.is_empty()
+    }
+
+    /// Adds the edge `from -> to` unless it already exists.
+    pub fn add_edge(&mut self, from: usize, to: usize) {
+        assert!(to < self.len(), "Node out of range");
+        if !self.successors[from].contains(&to) {
+            self.successors[from].push(to);
+        }
+    }
+
+    pub fn has_edge(&self, from: usize, to: usize) -> bool {
+        self.successors[from].contains(&to)
+    }
+
+    pub fn successors(&self, node: usize) -> &[usize] {
+        &self.successors[node]
+    }
+
+    /// Subgraph with only the given nodes and the edges between them. Node `i` of the result is
+    /// `nodes[i]` of this graph.
+    pub fn induced_subgraph(&self, nodes: &[usize]) -> DirectedGraph {
+        let mut position = vec![None; self.len()];
+        for (idx, node) in nodes.iter().enumerate() {
+            position[*node] = Some(idx);
+        }
+
+        let mut subgraph = DirectedGraph::new(nodes.len());
+        for (idx, node) in nodes.iter().enumerate() {
+            for succ in &self.successors[*node] {
+                if let Some(succ) = position[*succ] {
+                    subgraph.add_edge(idx, succ);
+                }
+            }
+        }
+        subgraph
+    }
+
+    /// Orders the nodes so every edge points forward with Kahn's algorithm.
+    pub fn topological_sort(&self) -> Result<TopologicalOrder, CycleError> {
+        let mut in_degree = vec![0; self.len()];
+        for succ in self.successors.iter().flatten() {
+            in_degree[*succ] += 1;
+        }
+
+        let mut ready = (0..self.len())
+            .filter(|node| in_degree[*node] == 0)
+            .map(Reverse)
+            .collect::<BinaryHeap<_>>();
+        let mut order = Vec::with_capacity(self.len());
+        let mut unique = true;
+
+        while let Some(Reverse(node)) = ready.pop() {
+            // Another node being ready at the same time could have been taken instead
+            unique &= ready.is_empty();
+            order.push(node);
+            for succ in &self.successors[node] {
+                in_degree[*succ] -= 1;
+                if in_degree[*succ] == 0 {
+                    ready.push(Reverse(*succ));
+                }
+            }
+        }
+
+        if order.len() < self.len() {
+            return Err(CycleError {
+                nodes: self.find_cycle(&in_degree),
+            });
+        }
+        Ok(TopologicalOrder { order, unique })
+    }
+
+    /// Finds a cycle among the nodes Kahn's algorithm could not remove. Each of them still has
+    /// a predecessor among them, so walking backwards eventually repeats a node.
+    fn find_cycle(&self, in_degree: &[usize]) -> Vec<usize> {
+        let mut predecessor = vec![None; self.len()];
+        for (node, successors) in self.successors.iter().enumerate() {
+            if in_degree[node] > 0 {
+                for succ in successors {
+                    predecessor[*succ] = Some(node);
+                }
+            }
+        }
+
+        let start = (0..self.len())
+            .find(|node| in_degree[*node] > 0)
+            .expect("Kahn's algorithm stopped early");
+        let mut step = vec![None; self.len()];
+        let mut path = Vec::new();
+        let mut node = start;
+        while step[node].is_none() {
+            step[node] = Some(path.len());
+            path.push(node);
+            node = predecessor[node].expect("Remaining nodes have a remaining predecessor");
+        }
+
+        // The path was walked backwards, turn it around and start at the lowest node
+        let mut cycle = path.split_off(step[node].unwrap_or(0));
+        cycle.reverse();
+        let lowest = (0..cycle.len()).min_by_key(|idx| cycle[*idx]).unwrap_or(0);
+        cycle.rotate_left(lowest);
+        cycle
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn graph(len: usize, edges: &[(usize, usize)]) -> DirectedGraph {
+        let mut g = DirectedGraph::new(len);
+        for (from, to) in edges {
+            g.add_edge(*from, *to);
+        }
+        g
+    }
+
+    #[test]
+    fn sort() {
+        let g = graph(6, &[(5, 2), (5, 0), (4, 0), (4, 1), (2, 3), (3, 1)]);
+        let sorted = g.topological_sort().unwrap();
+        assert_eq!(sorted.order, vec![4, 5, 0, 2, 3, 1]);
+        assert!(!sorted.unique);
+
+        let chain = graph(4, &[(2, 0), (0, 3), (3, 1), (2, 1)]);
+        let sorted = chain.topological_sort().unwrap();
+        assert_eq!(sorted.order, vec![2, 0, 3, 1]);
+        assert!(sorted.unique);
+    }
+
+    #[test]
+    fn cycle() {
+        let g = graph(5, &[(0, 1), (1, 2), (2, 3), (3, 1), (3, 4)]);
+        let err = g.topological_sort().unwrap_err();
+        let cycle = err.nodes;
+        assert_eq!(cycle.len(), 3);
+        for (idx, node) in cycle.iter().enumerate() {
+            assert!(g.has_edge(*node, cycle[(idx + 1) % cycle.len()]));
+        }
+
+        let err = graph(2, &[(0, 1), (1, 0)]).topological_sort().unwrap_err();
+        assert_eq!(err.to_string(), "Graph contains the cycle 0 -> 1 -> 0");
+    }
+
+    #[test]
+    fn induced_subgraph() {
+        let g = graph(5, &[(0, 1), (1, 2), (2, 0), (3, 4), (4, 2)]);
+        let sub = g.induced_subgraph(&[4, 2, 3]);
+        assert_eq!(sub, graph(3, &[(0, 1), (2, 0)]));
+        assert_eq!(sub.topological_sort().unwrap().order, vec![2, 0, 1]);
+    }
+}
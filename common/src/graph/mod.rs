@@ -0,0 +1,5 @@
+mod directed;
+mod undirected;
+
+pub use directed::{CycleError, DirectedGraph, TopologicalOrder};
+pub use undirected::UndirectedGraph;
@@ -0,0 +1,220 @@
+use crate::linear_algebra::BitVector;
+
+/// Simple undirected graph on the nodes `0..len` with one adjacency bitset per node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UndirectedGraph {
+    adjacency: Vec<BitVector>,
+}
+
+impl UndirectedGraph {
+    pub fn new(len: usize) -> Self {
+        Self {
+            adjacency: vec![BitVector::zeros(len); len],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.adjacency.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.adjacency.is_empty()
+    }
+
+    /// Connects `a` and `b`, loops are ignored.
+    pub fn add_edge(&mut self, a: usize, b: usize) {
+        if a != b {
+            self.adjacency[a].set(b, true);
+            self.adjacency[b].set(a, true);
+        }
+    }
+
+    pub fn has_edge(&self, a: usize, b: usize) -> bool {
+        self.adjacency[a].get(b)
+    }
+
+    pub fn neighbours(&self, node: usize) -> &BitVector {
+        &self.adjacency[node]
+    }
+
+    pub fn degree(&self, node: usize) -> usize {
+        self.adjacency[node].count_ones()
+    }
+
+    /// All cliques that can not be extended by another node, each sorted ascending.
+    ///
+    /// Bron–Kerbosch with the pivot chosen as the node with the most neighbours among the
+    /// candidates, which keeps the number of recursive calls within the bound of `3^(n/3)`
+    /// maximal cliques.
+    pub fn maximal_cliques(&self) -> Vec<Vec<usize>> {
+        let mut cliques = Vec::new();
+        if self.is_empty() {
+            return cliques;
+        }
+        let candidates = BitVector::from_ones(self.len(), 0..self.len());
+        self.bron_kerbosch(
+            &mut Vec::new(),
+            candidates,
+            BitVector::zeros(self.len()),
+            &mut cliques,
+        );
+        cliques
+    }
+
+    fn bron_kerbosch(
+        &self,
+        clique: &mut Vec<usize>,
+        mut candidates: BitVector,
+        mut excluded: BitVector,
+        cliques: &mut Vec<Vec<usize>>,
+    ) {
+        if candidates.is_zero() {
+            if excluded.is_zero() {
+                let mut clique = clique.clone();
+                clique.sort_unstable();
+                cliques.push(clique);
+            }
+            return;
+        }
+
+        // Every maximal clique contains the pivot or one of its non-neighbours
+        let pivot = candidates
+            .ones()
+            .chain(excluded.ones())
+            .max_by_key(|node| {
+                let mut shared = candidates.clone();
+                shared.and_assign(&self.adjacency[*node]);
+                shared.count_ones()
+            })
+            .expect("There is at least one candidate");
+        let mut branches = candidates.clone();
+        branches.and_not_assign(&self.adjacency[pivot]);
+
+        for node in branches.ones() {
+            let mut next_candidates = candidates.clone();
+            next_candidates.and_assign(&self.adjacency[node]);
+            let mut next_excluded = excluded.clone();
+            next_excluded.and_assign(&self.adjacency[node]);
+
+            clique.push(node);
+            self.bron_kerbosch(clique, next_candidates, next_excluded, cliques);
+            clique.pop();
+
+            candidates.set(node, false);
+            excluded.set(node, true);
+        }
+    }
+
+    /// A clique with the most nodes, sorted ascending. Ties go to the first one found.
+    pub fn maximum_clique(&self) -> Vec<usize> {
+        self.maximal_cliques()
+            .into_iter()
+            .rev()
+            .max_by_key(Vec::len)
+            .unwrap_or_default()
+    }
+
+    /// All cliques with exactly `k` nodes, each sorted ascending and in lexicographic order.
+    pub fn cliques_of_size(&self, k: usize) -> Vec<Vec<usize>> {
+        let mut cliques = Vec::new();
+        let candidates = BitVector::from_ones(self.len(), 0..self.len());
+        self.extend_cliques(&mut Vec::with_capacity(k), candidates, k, &mut cliques);
+        cliques
+    }
+
+    fn extend_cliques(
+        &self,
+        clique: &mut Vec<usize>,
+        mut candidates: BitVector,
+        k: usize,
+        cliques: &mut Vec<Vec<usize>>,
+    ) {
+        if clique.len() == k {
+            cliques.push(clique.clone());
+            return;
+        }
+
+        // Only extend with higher nodes than the ones already in the clique so every clique is
+        // found exactly once
+        for node in candidates.clone().ones() {
+            candidates.set(node, false);
+            if clique.len() + 1 + candidates.count_ones() < k {
+                break;
+            }
+            let mut next = candidates.clone();
+            next.and_assign(&self.adjacency[node]);
+
+            clique.push(node);
+            self.extend_cliques(clique, next, k, cliques);
+            clique.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn graph(len: usize, edges: &[(usize, usize)]) -> UndirectedGraph {
+        let mut g = UndirectedGraph::new(len);
+        for (a, b) in edges {
+            g.add_edge(*a, *b);
+        }
+        g
+    }
+
+    #[test]
+    fn maximal_cliques() {
+        // Two triangles sharing the edge 1-2, a square 3-4-5-6 and the isolated node 7
+        let g = graph(
+            8,
+            &[
+                (0, 1),
+                (0, 2),
+                (1, 2),
+                (1, 3),
+                (2, 3),
+                (3, 4),
+                (4, 5),
+                (5, 6),
+                (6, 3),
+            ],
+        );
+        let mut cliques = g.maximal_cliques();
+        cliques.sort();
+        assert_eq!(
+            cliques,
+            vec![
+                vec![0, 1, 2],
+                vec![1, 2, 3],
+                vec![3, 4],
+                vec![3, 6],
+                vec![4, 5],
+                vec![5, 6],
+                vec![7],
+            ]
+        );
+        assert_eq!(g.maximum_clique().len(), 3);
+        assert_eq!(g.degree(3), 4);
+    }
+
+    #[test]
+    fn complete_graph() {
+        let edges = (0..6)
+            .flat_map(|a| (a + 1..6).map(move |b| (a, b)))
+            .collect::<Vec<_>>();
+        let g = graph(6, &edges);
+        assert_eq!(g.maximal_cliques(), vec![vec![0, 1, 2, 3, 4, 5]]);
+        assert_eq!(g.cliques_of_size(3).len(), 20);
+        assert_eq!(g.cliques_of_size(6).len(), 1);
+        assert_eq!(g.cliques_of_size(7).len(), 0);
+    }
+
+    #[test]
+    fn triangles() {
+        let g = graph(5, &[(0, 1), (1, 2), (2, 0), (2, 3), (3, 0), (3, 4)]);
+        assert_eq!(g.cliques_of_size(3), vec![vec![0, 1, 2], vec![0, 2, 3]]);
+        assert_eq!(g.cliques_of_size(2).len(), 6);
+        assert!(UndirectedGraph::new(0).maximal_cliques().is_empty());
+    }
+}
@@ -0,0 +1,351 @@
+use std::fmt::Debug;
+use std::ops::{Add, Bound, RangeBounds, RangeInclusive, Sub};
+
+/// Integer types that can be used as bounds of an [`IntervalSet`].
+pub trait Discrete: Copy + Ord + Debug + Add<Output = Self> + Sub<Output = Self> {
+    const ZERO: Self;
+    const ONE: Self;
+    const MIN: Self;
+    const MAX: Self;
+
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+
+    fn succ(self) -> Option<Self> {
+        (self != Self::MAX).then(|| self + Self::ONE)
+    }
+
+    fn pred(self) -> Option<Self> {
+        (self != Self::MIN).then(|| self - Self::ONE)
+    }
+}
+
+macro_rules! impl_discrete {
+    ($($t:ty),*) => {
+        $(
+            impl Discrete for $t {
+                const ZERO: Self = 0;
+                const ONE: Self = 1;
+                const MIN: Self = <$t>::MIN;
+                const MAX: Self = <$t>::MAX;
+
+                fn checked_add(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_add(self, rhs)
+                }
+
+                fn checked_sub(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_sub(self, rhs)
+                }
+            }
+        )*
+    };
+}
+
+impl_discrete!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// Converts any range into its inclusive `(start, end)` form, `None` if it is empty.
+fn to_inclusive<T: Discrete>(range: impl RangeBounds<T>) -> Option<(T, T)> {
+    let start = match range.start_bound() {
+        Bound::Included(start) => *start,
+        Bound::Excluded(start) => start.succ()?,
+        Bound::Unbounded => T::MIN,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(end) => *end,
+        Bound::Excluded(end) => end.pred()?,
+        Bound::Unbounded => T::MAX,
+    };
+    (start <= end).then_some((start, end))
+}
+
+/// Set of integers stored as sorted, disjoint and non-adjacent inclusive intervals.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct IntervalSet<T> {
+    intervals: Vec<(T, T)>,
+}
+
+impl<T: Discrete> Default for IntervalSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Discrete> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self {
+            intervals: Vec::new(),
+        }
+    }
+
+    /// Sorts the intervals and merges the ones that overlap or touch each other.
+    fn normalized(mut intervals: Vec<(T, T)>) -> Self {
+        intervals.sort_unstable();
+
+        let mut merged: Vec<(T, T)> = Vec::with_capacity(intervals.len());
+        for (start, end) in intervals {
+            match merged.last_mut() {
+                Some(prev) if prev.1.succ().is_none_or(|next| start <= next) => {
+                    prev.1 = prev.1.max(end);
+                }
+                _ => merged.push((start, end)),
+            }
+        }
+
+        Self { intervals: merged }
+    }
+
+    pub fn insert(&mut self, range: impl RangeBounds<T>) {
+        let Some((mut start, mut end)) = to_inclusive(range) else {
+            return;
+        };
+
+        // All intervals in `first..last` overlap or touch the new one and are merged into it
+        let first = self
+            .intervals
+            .partition_point(|(_, e)| e.succ().is_some_and(|next| next < start));
+        let last = self
+            .intervals
+            .partition_point(|(s, _)| end.succ().is_none_or(|next| *s <= next));
+
+        if first < last {
+            start = start.min(self.intervals[first].0);
+            end = end.max(self.intervals[last - 1].1);
+        }
+        self.intervals.splice(first..last, [(start, end)]);
+    }
+
+    pub fn remove(&mut self, range: impl RangeBounds<T>) {
+        if let Some((start, end)) = to_inclusive(range) {
+            *self = self.difference(&Self {
+                intervals: vec![(start, end)],
+            });
+        }
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let idx = self.intervals.partition_point(|(start, _)| *start <= value);
+        idx > 0 && value <= self.intervals[idx - 1].1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// Number of integers contained in the set, `None` if it does not fit into `T` like for the
+    /// full domain.
+    pub fn len(&self) -> Option<T> {
+        self.intervals
+            .iter()
+            .try_fold(T::ZERO, |acc, (start, end)| {
+                end.checked_sub(*start)?
+                    .checked_add(T::ONE)?
+                    .checked_add(acc)
+            })
+    }
+
+    /// Number of disjoint intervals the set is made of.
+    pub fn interval_count(&self) -> usize {
+        self.intervals.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = RangeInclusive<T>> + '_ {
+        self.intervals.iter().map(|(start, end)| *start..=*end)
+    }
+
+    /// Iterates every single integer contained in the set in ascending order.
+    pub fn values(&self) -> impl Iterator<Item = T> + '_ {
+        self.intervals.iter().flat_map(|(start, end)| {
+            let end = *end;
+            std::iter::successors(Some(*start), move |curr| {
+                curr.succ().filter(|next| *next <= end)
+            })
+        })
+    }
+
+    /// Iterates the holes between two consecutive intervals of the set.
+    pub fn gaps(&self) -> impl Iterator<Item = RangeInclusive<T>> + '_ {
+        // Intervals never touch, so there is at least one integer between two of them
+        self.intervals
+            .windows(2)
+            .map(|pair| pair[0].1 + T::ONE..=pair[1].0 - T::ONE)
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self::normalized(
+            self.intervals
+                .iter()
+                .chain(other.intervals.iter())
+                .copied()
+                .collect(),
+        )
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut intervals = Vec::new();
+        let (mut i, mut j) = (0, 0);
+
+        while i < self.intervals.len() && j < other.intervals.len() {
+            let (a_start, a_end) = self.intervals[i];
+            let (b_start, b_end) = other.intervals[j];
+
+            let start = a_start.max(b_start);
+            let end = a_end.min(b_end);
+            if start <= end {
+                intervals.push((start, end));
+            }
+
+            // Advance the interval that ends first since it can not overlap anything else
+            if a_end < b_end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+
+        Self { intervals }
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        self.intersection(&other.complement(..))
+    }
+
+    /// All integers of `universe` that are not contained in the set.
+    pub fn complement(&self, universe: impl RangeBounds<T>) -> Self {
+        let Some((start, end)) = to_inclusive(universe) else {
+            return Self::new();
+        };
+
+        let mut intervals = Vec::new();
+        let mut next_free = Some(start);
+
+        for (s, e) in self.intervals.iter() {
+            let Some(free) = next_free else {
+                break;
+            };
+            if *s > end {
+                break;
+            }
+            if free < *s {
+                intervals.push((free, *s - T::ONE));
+            }
+            if *e >= free {
+                next_free = e.succ();
+            }
+        }
+
+        if let Some(free) = next_free.filter(|free| *free <= end) {
+            intervals.push((free, end));
+        }
+
+        Self { intervals }
+    }
+}
+
+impl<T: Discrete, R: RangeBounds<T>> FromIterator<R> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = R>>(iter: I) -> Self {
+        Self::normalized(iter.into_iter().filter_map(to_inclusive).collect())
+    }
+}
+
+impl<T: Discrete, R: RangeBounds<T>> Extend<R> for IntervalSet<T> {
+    fn extend<I: IntoIterator<Item = R>>(&mut self, iter: I) {
+        let mut intervals = std::mem::take(&mut self.intervals);
+        intervals.extend(iter.into_iter().filter_map(to_inclusive));
+        *self = Self::normalized(intervals);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ranges<T: Discrete>(set: &IntervalSet<T>) -> Vec<RangeInclusive<T>> {
+        set.iter().collect()
+    }
+
+    #[test]
+    fn normalize() {
+        let set = [3..=5, 10..=14, 16..=20, 12..=18]
+            .into_iter()
+            .collect::<IntervalSet<usize>>();
+        assert_eq!(ranges(&set), vec![3..=5, 10..=20]);
+        assert_eq!(set.len(), Some(14));
+
+        // Adjacent intervals are merged, empty ones are dropped
+        let set = IntervalSet::from_iter([
+            (Bound::Included(1), Bound::Excluded(4)),
+            (Bound::Included(4), Bound::Included(6)),
+            (Bound::Excluded(8), Bound::Excluded(9)),
+        ]);
+        assert_eq!(ranges(&set), vec![1..=6]);
+    }
+
+    #[test]
+    fn insert() {
+        let mut set = IntervalSet::new();
+        set.insert(10..20);
+        set.insert(30..=40);
+        set.insert(0..5);
+        assert_eq!(ranges(&set), vec![0..=4, 10..=19, 30..=40]);
+
+        set.insert(5..10);
+        assert_eq!(ranges(&set), vec![0..=19, 30..=40]);
+
+        set.insert(25..27);
+        set.insert(18..=50);
+        assert_eq!(ranges(&set), vec![0..=50]);
+
+        let mut set = IntervalSet::new();
+        set.insert(u8::MAX - 1..);
+        set.insert(..=1u8);
+        set.insert(2..u8::MAX - 1);
+        assert_eq!(ranges(&set), vec![0..=u8::MAX]);
+        assert_eq!(set.len(), None);
+
+        let mut set = IntervalSet::new();
+        set.insert(-100..=-1i8);
+        set.insert(1..=27);
+        assert_eq!(set.len(), Some(i8::MAX));
+        set.insert(0..=0);
+        assert_eq!(set.len(), None);
+    }
+
+    #[test]
+    fn contains() {
+        let set = [3..=5, 10..=14].into_iter().collect::<IntervalSet<i32>>();
+        let contained = (0..16).filter(|v| set.contains(*v)).collect::<Vec<_>>();
+        assert_eq!(contained, vec![3, 4, 5, 10, 11, 12, 13, 14]);
+        assert_eq!(set.values().collect::<Vec<_>>(), contained);
+    }
+
+    #[test]
+    fn set_operations() {
+        let a = [0..=10, 20..=30].into_iter().collect::<IntervalSet<i64>>();
+        let b = [5..=25, 40..=50].into_iter().collect::<IntervalSet<i64>>();
+
+        assert_eq!(ranges(&a.union(&b)), vec![0..=30, 40..=50]);
+        assert_eq!(ranges(&a.intersection(&b)), vec![5..=10, 20..=25]);
+        assert_eq!(ranges(&a.difference(&b)), vec![0..=4, 26..=30]);
+        assert_eq!(ranges(&b.difference(&a)), vec![11..=19, 40..=50]);
+        assert_eq!(
+            ranges(&a.complement(-5..45)),
+            vec![-5..=-1, 11..=19, 31..=44]
+        );
+        assert_eq!(ranges(&a.complement(12..=18)), vec![12..=18]);
+        assert!(a.complement(0..=10).is_empty());
+
+        let mut c = a.clone();
+        c.remove(3..23);
+        assert_eq!(ranges(&c), vec![0..=2, 23..=30]);
+    }
+
+    #[test]
+    fn gaps() {
+        let set = [0..=2, 5..=5, 7..=9]
+            .into_iter()
+            .collect::<IntervalSet<u32>>();
+        assert_eq!(set.gaps().collect::<Vec<_>>(), vec![3..=4, 6..=6]);
+        assert_eq!(set.interval_count(), 3);
+    }
+}
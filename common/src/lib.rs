@@ -0,0 +1,8 @@
+pub mod cycle;
+pub mod exact_cover;
+pub mod geometry;
+pub mod graph;
+pub mod interval_set;
+pub mod linear_algebra;
+pub mod number_theory;
+pub mod union_find;
@@ -0,0 +1,443 @@
+use std::fmt::Display;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// Fixed length vector over GF(2) packed into 64 bit words.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BitVector {
+    len: usize,
+    words: Vec<u64>,
+}
+
+impl BitVector {
+    pub fn zeros(len: usize) -> Self {
+        Self {
+            len,
+            words: vec![0; len.div_ceil(WORD_BITS)],
+        }
+    }
+
+    /// Vector of length `len` with exactly the bits at `ones` set.
+    pub fn from_ones(len: usize, ones: impl IntoIterator<Item = usize>) -> Self {
+        let mut v = Self::zeros(len);
+        for idx in ones {
+            v.set(idx, true);
+        }
+        v
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, idx: usize) -> bool {
+        assert!(idx < self.len, "Bit index out of range");
+        self.words[idx / WORD_BITS] >> (idx % WORD_BITS) & 1 == 1
+    }
+
+    pub fn set(&mut self, idx: usize, value: bool) {
+        assert!(idx < self.len, "Bit index out of range");
+        let mask = 1 << (idx % WORD_BITS);
+        if value {
+            self.words[idx / WORD_BITS] |= mask;
+        } else {
+            self.words[idx / WORD_BITS] &= !mask;
+        }
+    }
+
+    pub fn toggle(&mut self, idx: usize) {
+        assert!(idx < self.len, "Bit index out of range");
+        self.words[idx / WORD_BITS] ^= 1 << (idx % WORD_BITS);
+    }
+
+    /// Adds `other` to this vector, which over GF(2) is a bitwise xor.
+    pub fn xor_assign(&mut self, other: &BitVector) {
+        assert_eq!(self.len, other.len);
+        for (a, b) in self.words.iter_mut().zip(other.words.iter()) {
+            *a ^= b;
+        }
+    }
+
+    /// Keeps only the bits that are also set in `other`, which over GF(2) is the component-wise
+    /// product.
+    pub fn and_assign(&mut self, other: &BitVector) {
+        assert_eq!(self.len, other.len);
+        for (a, b) in self.words.iter_mut().zip(other.words.iter()) {
+            *a &= b;
+        }
+    }
+
+    /// Clears all bits that are set in `other`.
+    pub fn and_not_assign(&mut self, other: &BitVector) {
+        assert_eq!(self.len, other.len);
+        for (a, b) in self.words.iter_mut().zip(other.words.iter()) {
+            *a &= !b;
+        }
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.words.iter().all(|w| *w == 0)
+    }
+
+    /// Indices of all set bits in ascending order.
+    pub fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(i, word)| {
+            let mut word = *word;
+            std::iter::from_fn(move || {
+                (word != 0).then(|| {
+                    let bit = word.trailing_zeros() as usize;
+                    word &= word - 1;
+                    i * WORD_BITS + bit
+                })
+            })
+        })
+    }
+}
+
+impl Display for BitVector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for idx in 0..self.len {
+            write!(f, "{}", if self.get(idx) { '1' } else { '0' })?;
+        }
+        Ok(())
+    }
+}
+
+/// Matrix over GF(2) stored as one [`BitVector`] per row.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Gf2Matrix {
+    cols: usize,
+    rows: Vec<BitVector>,
+}
+
+/// Every solution of `A * x = b` over GF(2): `particular` plus the sum of any subset of the
+/// `null_space` vectors.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Gf2Solution {
+    pub particular: BitVector,
+    pub null_space: Vec<BitVector>,
+}
+
+impl Gf2Solution {
+    /// Number of distinct solutions, `None` if it does not fit into a `u128`.
+    pub fn count(&self) -> Option<u128> {
+        1_u128.checked_shl(self.null_space.len() as u32)
+    }
+
+    /// Iterates all `2^k` solutions for a null space of dimension `k`.
+    pub fn solutions(&self) -> impl Iterator<Item = BitVector> + '_ {
+        // Walk the subsets in Gray code order so each step adds exactly one basis vector, the walk
+        // is done once the step would add a vector beyond the basis
+        let mut curr = self.particular.clone();
+        let mut step = 0_u128;
+        std::iter::from_fn(move || {
+            if step > 0 {
+                curr.xor_assign(self.null_space.get(step.trailing_zeros() as usize)?);
+            }
+            step += 1;
+            Some(curr.clone())
+        })
+    }
+
+    /// Solution with the fewest set bits.
+    ///
+    /// Branch and bound over the null space in reduced row echelon form. Every basis vector is the
+    /// only one with a bit at its pivot column, so the pivot bits decided so far are a lower bound
+    /// of the weight and subtrees that can not beat the best solution found so far are skipped.
+    pub fn min_weight(&self) -> BitVector {
+        let (reduced, pivots) =
+            Gf2Matrix::from_rows(self.particular.len(), self.null_space.iter().cloned()).rref();
+        let mut best = (self.particular.count_ones(), self.particular.clone());
+        Self::branch_and_bound(
+            &reduced.rows[..pivots.len()],
+            &pivots,
+            &mut self.particular.clone(),
+            0,
+            &mut best,
+        );
+        best.1
+    }
+
+    fn branch_and_bound(
+        basis: &[BitVector],
+        pivots: &[usize],
+        curr: &mut BitVector,
+        fixed_ones: usize,
+        best: &mut (usize, BitVector),
+    ) {
+        if fixed_ones >= best.0 {
+            return;
+        }
+        let Some((vector, rest)) = basis.split_first() else {
+            let weight = curr.count_ones();
+            if weight < best.0 {
+                *best = (weight, curr.clone());
+            }
+            return;
+        };
+
+        // Clearing the pivot bit first finds light solutions early, which prunes more
+        let pivot_set = curr.get(pivots[0]);
+        for add in [pivot_set, !pivot_set] {
+            if add {
+                curr.xor_assign(vector);
+            }
+            let ones = fixed_ones + curr.get(pivots[0]) as usize;
+            Self::branch_and_bound(rest, &pivots[1..], curr, ones, best);
+            if add {
+                curr.xor_assign(vector);
+            }
+        }
+    }
+}
+
+impl Gf2Matrix {
+    pub fn zeros(rows: usize, cols: usize) -> Self {
+        Self {
+            cols,
+            rows: vec![BitVector::zeros(cols); rows],
+        }
+    }
+
+    /// Builds a matrix from its rows, panics if a row does not have `cols` bits.
+    pub fn from_rows(cols: usize, rows: impl IntoIterator<Item = BitVector>) -> Self {
+        let rows = rows.into_iter().collect::<Vec<_>>();
+        assert!(rows.iter().all(|row| row.len() == cols));
+        Self { cols, rows }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn row(&self, row: usize) -> &BitVector {
+        &self.rows[row]
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> bool {
+        self.rows[row].get(col)
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, value: bool) {
+        self.rows[row].set(col, value);
+    }
+
+    pub fn mul_vec(&self, v: &BitVector) -> BitVector {
+        assert_eq!(v.len(), self.cols);
+
+        let mut product = BitVector::zeros(self.rows());
+        for (idx, row) in self.rows.iter().enumerate() {
+            let parity = row
+                .words
+                .iter()
+                .zip(v.words.iter())
+                .map(|(a, b)| (a & b).count_ones())
+                .sum::<u32>();
+            product.set(idx, parity % 2 == 1);
+        }
+        product
+    }
+
+    /// Appends `column` as an additional rightmost column.
+    pub fn augment(&self, column: &BitVector) -> Self {
+        assert_eq!(column.len(), self.rows());
+
+        let rows = self.rows.iter().enumerate().map(|(idx, row)| {
+            let mut extended = BitVector::zeros(self.cols + 1);
+            extended.words[..row.words.len()].copy_from_slice(&row.words);
+            extended.set(self.cols, column.get(idx));
+            extended
+        });
+        Self::from_rows(self.cols + 1, rows)
+    }
+
+    /// Reduced row echelon form, together with the pivot columns.
+    pub fn rref(&self) -> (Gf2Matrix, Vec<usize>) {
+        let mut m = self.clone();
+        let mut pivots = Vec::new();
+
+        for col in 0..m.cols {
+            let pivot_row = pivots.len();
+            if pivot_row == m.rows() {
+                break;
+            }
+            let Some(row) = (pivot_row..m.rows()).find(|row| m.get(*row, col)) else {
+                continue;
+            };
+            m.rows.swap(row, pivot_row);
+
+            let pivot = m.rows[pivot_row].clone();
+            for (idx, other) in m.rows.iter_mut().enumerate() {
+                if idx != pivot_row && other.get(col) {
+                    other.xor_assign(&pivot);
+                }
+            }
+            pivots.push(col);
+        }
+
+        (m, pivots)
+    }
+
+    pub fn rank(&self) -> usize {
+        self.rref().1.len()
+    }
+
+    /// Basis of all vectors `x` with `A * x = 0`.
+    pub fn null_space(&self) -> Vec<BitVector> {
+        let (reduced, pivots) = self.rref();
+        Self::null_space_from_rref(&reduced, &pivots, self.cols)
+    }
+
+    fn null_space_from_rref(reduced: &Gf2Matrix, pivots: &[usize], cols: usize) -> Vec<BitVector> {
+        (0..cols)
+            .filter(|col| !pivots.contains(col))
+            .map(|free| {
+                let mut v = BitVector::zeros(cols);
+                v.set(free, true);
+                for (row, pivot) in pivots.iter().enumerate() {
+                    v.set(*pivot, reduced.get(row, free));
+                }
+                v
+            })
+            .collect()
+    }
+
+    /// Solves `A * x = rhs`, `None` if the system is inconsistent.
+    pub fn solve(&self, rhs: &BitVector) -> Option<Gf2Solution> {
+        let (reduced, pivots) = self.augment(rhs).rref();
+
+        // A pivot in the augmented column means a row reads `0 = 1`
+        if pivots.last() == Some(&self.cols) {
+            return None;
+        }
+
+        let mut particular = BitVector::zeros(self.cols);
+        for (row, pivot) in pivots.iter().enumerate() {
+            particular.set(*pivot, reduced.get(row, self.cols));
+        }
+
+        Some(Gf2Solution {
+            particular,
+            null_space: Self::null_space_from_rref(&reduced, &pivots, self.cols),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn matrix(rows: &[&str]) -> Gf2Matrix {
+        let cols = rows.first().map(|row| row.len()).unwrap_or(0);
+        Gf2Matrix::from_rows(
+            cols,
+            rows.iter().map(|row| {
+                BitVector::from_ones(
+                    cols,
+                    row.chars()
+                        .enumerate()
+                        .filter(|(_, c)| *c == '1')
+                        .map(|(i, _)| i),
+                )
+            }),
+        )
+    }
+
+    fn bits(s: &str) -> BitVector {
+        matrix(&[s]).row(0).clone()
+    }
+
+    #[test]
+    fn bit_vector() {
+        let mut v = BitVector::from_ones(130, [0, 64, 129]);
+        assert_eq!(v.count_ones(), 3);
+        v.toggle(64);
+        v.toggle(65);
+        assert_eq!(v.ones().collect::<Vec<_>>(), vec![0, 65, 129]);
+        assert!(!v.get(64));
+        assert!(v.get(129));
+
+        let mask = BitVector::from_ones(130, [0, 1, 129]);
+        let mut both = v.clone();
+        both.and_assign(&mask);
+        assert_eq!(both.ones().collect::<Vec<_>>(), vec![0, 129]);
+        v.and_not_assign(&mask);
+        assert_eq!(v.ones().collect::<Vec<_>>(), vec![65]);
+    }
+
+    #[test]
+    fn rref_and_rank() {
+        let m = matrix(&["110", "011", "101"]);
+        let (reduced, pivots) = m.rref();
+        assert_eq!(reduced, matrix(&["101", "011", "000"]));
+        assert_eq!(pivots, vec![0, 1]);
+        assert_eq!(m.rank(), 2);
+        assert_eq!(m.null_space(), vec![bits("111")]);
+    }
+
+    #[test]
+    fn minimal_toggles() {
+        // [.##.] (3) (1,3) (2) (2,3) (0,2) (0,1): rows are lights, columns are buttons
+        let m = matrix(&["000011", "010001", "001110", "110100"]);
+        let target = bits("0110");
+        let solution = m.solve(&target).unwrap();
+
+        assert_eq!(solution.count(), Some(4));
+        for x in solution.solutions() {
+            assert_eq!(m.mul_vec(&x), target);
+        }
+        assert_eq!(solution.min_weight().count_ones(), 2);
+    }
+
+    #[test]
+    fn inconsistent() {
+        let m = matrix(&["11", "11"]);
+        assert!(m.solve(&bits("10")).is_none());
+        assert_eq!(m.solve(&bits("11")).unwrap().count(), Some(2));
+    }
+
+    #[test]
+    fn min_weight_matches_enumeration() {
+        let m = matrix(&[
+            "1011001110",
+            "0110110011",
+            "1100101101",
+            "0011011110",
+            "1110000111",
+        ]);
+        for target in ["10110", "01101", "11111", "00000"] {
+            let target = bits(target);
+            let solution = m.solve(&target).unwrap();
+            let min = solution.min_weight();
+            assert_eq!(m.mul_vec(&min), target);
+            assert_eq!(
+                min.count_ones(),
+                solution.solutions().map(|x| x.count_ones()).min().unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn large_null_space() {
+        // Any single bit solves a row of all ones, with a null space of dimension 199
+        let m = Gf2Matrix::from_rows(200, [BitVector::from_ones(200, 0..200)]);
+        let solution = m.solve(&bits("1")).unwrap();
+        assert_eq!(solution.count(), None);
+        assert_eq!(solution.min_weight().count_ones(), 1);
+
+        let m = Gf2Matrix::from_rows(128, [BitVector::from_ones(128, 0..128)]);
+        assert_eq!(m.solve(&bits("1")).unwrap().count(), Some(1 << 127));
+    }
+}
@@ -0,0 +1,253 @@
+use super::{Matrix, Rational};
+use crate::number_theory::lcm;
+
+/// Bounded integer program `A * x = b` with `0 <= x <= upper_bounds` for integer vectors `x`.
+///
+/// The equality constraints are eliminated first so only the free variables of the system are
+/// searched, every pivot variable then follows from them. The search is a branch and bound over
+/// the free variables that cuts partial assignments which can no longer keep every pivot variable
+/// within its bounds or can not beat the best objective found so far.
+#[derive(Clone, Debug)]
+pub struct IntegerProgram {
+    constraints: Matrix,
+    rhs: Vec<Rational>,
+    upper_bounds: Vec<i128>,
+}
+
+/// Pivot variable of the eliminated system: `den * x[pivot] = rhs - sum(coeffs[k] * t[k])`
+/// where `t` are the free variables.
+#[derive(Debug)]
+struct PivotRow {
+    pivot: usize,
+    den: i128,
+    rhs: i128,
+    coeffs: Vec<i128>,
+}
+
+struct Search<'a> {
+    rows: Vec<PivotRow>,
+    free_variables: Vec<usize>,
+    upper_bounds: &'a [i128],
+    costs: &'a [i128],
+    /// Change of the objective when a free variable is increased by one.
+    gradient: Vec<Rational>,
+    /// Per row the lowest and highest value `sum(coeffs[k] * t[k])` can take for `k >= depth`.
+    remaining: Vec<Vec<(i128, i128)>>,
+    /// Lowest change of the objective the free variables `k >= depth` can still contribute.
+    remaining_gradient: Vec<Rational>,
+    best: Option<(Rational, Vec<i128>)>,
+}
+
+impl IntegerProgram {
+    pub fn new(constraints: Matrix, rhs: &[i128], upper_bounds: &[i128]) -> Self {
+        assert_eq!(constraints.rows(), rhs.len());
+        assert_eq!(constraints.cols(), upper_bounds.len());
+
+        Self {
+            constraints,
+            rhs: rhs.iter().map(|v| Rational::from(*v)).collect(),
+            upper_bounds: upper_bounds.to_vec(),
+        }
+    }
+
+    /// Feasible `x` minimizing `costs * x` together with that minimal cost, `None` if there is
+    /// no integer solution within the bounds.
+    pub fn minimize(&self, costs: &[i128]) -> Option<(i128, Vec<i128>)> {
+        assert_eq!(costs.len(), self.constraints.cols());
+
+        let solution = self.constraints.solve(&self.rhs)?;
+        let pivots = (0..self.constraints.cols())
+            .filter(|col| !solution.free_variables.contains(col))
+            .collect::<Vec<_>>();
+
+        // Bring every pivot equation to integer form by multiplying with the common denominator
+        let rows = pivots
+            .iter()
+            .map(|pivot| {
+                let coeffs = solution
+                    .null_space
+                    .iter()
+                    .map(|basis| -basis[*pivot])
+                    .collect::<Vec<_>>();
+                let rhs = solution.particular[*pivot];
+                let den = coeffs
+                    .iter()
+                    .chain([&rhs])
+                    .fold(1, |acc, r| lcm(acc, r.denom()));
+                PivotRow {
+                    pivot: *pivot,
+                    den,
+                    rhs: (rhs * Rational::from(den)).numer(),
+                    coeffs: coeffs
+                        .iter()
+                        .map(|c| (*c * Rational::from(den)).numer())
+                        .collect(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let free_count = solution.free_variables.len();
+        let gradient = solution
+            .null_space
+            .iter()
+            .map(|basis| {
+                basis
+                    .iter()
+                    .zip(costs)
+                    .map(|(b, c)| *b * Rational::from(*c))
+                    .sum()
+            })
+            .collect::<Vec<Rational>>();
+
+        let mut remaining = vec![vec![(0, 0); free_count + 1]; rows.len()];
+        for (row, bounds) in rows.iter().zip(remaining.iter_mut()) {
+            for k in (0..free_count).rev() {
+                let extreme = row.coeffs[k] * self.upper_bounds[solution.free_variables[k]];
+                let (lo, hi) = bounds[k + 1];
+                bounds[k] = (lo + extreme.min(0), hi + extreme.max(0));
+            }
+        }
+
+        let mut remaining_gradient = vec![Rational::ZERO; free_count + 1];
+        for k in (0..free_count).rev() {
+            let extreme =
+                gradient[k] * Rational::from(self.upper_bounds[solution.free_variables[k]]);
+            remaining_gradient[k] = remaining_gradient[k + 1] + extreme.min(Rational::ZERO);
+        }
+
+        let base_cost = solution
+            .particular
+            .iter()
+            .zip(costs)
+            .map(|(x, c)| *x * Rational::from(*c))
+            .sum();
+
+        let mut search = Search {
+            rows,
+            free_variables: solution.free_variables,
+            upper_bounds: &self.upper_bounds,
+            costs,
+            gradient,
+            remaining,
+            remaining_gradient,
+            best: None,
+        };
+        let partial = search.rows.iter().map(|row| row.rhs).collect::<Vec<_>>();
+        search.descend(&mut Vec::with_capacity(free_count), &partial, base_cost);
+
+        search.best.map(|(cost, x)| {
+            let cost = cost
+                .to_integer()
+                .expect("Integer solutions have integer costs");
+            (cost, x)
+        })
+    }
+}
+
+impl Search<'_> {
+    fn descend(&mut self, assigned: &mut Vec<i128>, partial: &[i128], cost: Rational) {
+        let depth = assigned.len();
+
+        if self
+            .best
+            .as_ref()
+            .is_some_and(|(best, _)| cost + self.remaining_gradient[depth] >= *best)
+        {
+            return;
+        }
+
+        // Every pivot variable must still be able to reach a value within its bounds
+        for ((row, bounds), value) in self.rows.iter().zip(self.remaining.iter()).zip(partial) {
+            let (lo, hi) = bounds[depth];
+            if value - lo < 0 || value - hi > row.den * self.upper_bounds[row.pivot] {
+                return;
+            }
+        }
+
+        if depth == self.free_variables.len() {
+            self.complete(assigned, partial, cost);
+            return;
+        }
+
+        let upper = self.upper_bounds[self.free_variables[depth]];
+        let mut next_partial = partial.to_vec();
+        for value in 0..=upper {
+            for (next, (row, curr)) in next_partial.iter_mut().zip(self.rows.iter().zip(partial)) {
+                *next = curr - row.coeffs[depth] * value;
+            }
+            assigned.push(value);
+            self.descend(
+                assigned,
+                &next_partial,
+                cost + self.gradient[depth] * Rational::from(value),
+            );
+            assigned.pop();
+        }
+    }
+
+    fn complete(&mut self, assigned: &[i128], partial: &[i128], cost: Rational) {
+        let mut x = vec![0; self.costs.len()];
+        for (free, value) in self.free_variables.iter().zip(assigned) {
+            x[*free] = *value;
+        }
+        for (row, value) in self.rows.iter().zip(partial) {
+            if value % row.den != 0 {
+                return;
+            }
+            x[row.pivot] = value / row.den;
+        }
+        self.best = Some((cost, x));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn buttons_to_matrix(counters: usize, buttons: &[&[usize]]) -> Matrix {
+        Matrix::from_rows((0..counters).map(|counter| {
+            buttons
+                .iter()
+                .map(move |button| i128::from(button.contains(&counter)))
+        }))
+    }
+
+    #[test]
+    fn minimal_presses() {
+        // (3) (1,3) (2) (2,3) (0,2) (0,1) {3,5,4,7}
+        let buttons: [&[usize]; 6] = [&[3], &[1, 3], &[2], &[2, 3], &[0, 2], &[0, 1]];
+        let program = IntegerProgram::new(buttons_to_matrix(4, &buttons), &[3, 5, 4, 7], &[7; 6]);
+        let (presses, x) = program.minimize(&[1; 6]).unwrap();
+        assert_eq!(presses, 10);
+        assert_eq!(x.iter().sum::<i128>(), 10);
+
+        // (0,2,3,4) (2,3) (0,4) (0,1,2) (1,2,3,4) {7,5,12,7,2}
+        let buttons: [&[usize]; 5] = [&[0, 2, 3, 4], &[2, 3], &[0, 4], &[0, 1, 2], &[1, 2, 3, 4]];
+        let program =
+            IntegerProgram::new(buttons_to_matrix(5, &buttons), &[7, 5, 12, 7, 2], &[12; 5]);
+        assert_eq!(program.minimize(&[1; 5]).unwrap().0, 12);
+    }
+
+    #[test]
+    fn weighted_costs() {
+        // x + y = 4 with y being cheaper than x
+        let program = IntegerProgram::new(Matrix::from_rows([[1, 1]]), &[4], &[4, 4]);
+        assert_eq!(program.minimize(&[3, 1]), Some((4, vec![0, 4])));
+        assert_eq!(program.minimize(&[1, 3]), Some((4, vec![4, 0])));
+    }
+
+    #[test]
+    fn infeasible() {
+        // 2x = 3 has no integer solution
+        let program = IntegerProgram::new(Matrix::from_rows([[2]]), &[3], &[10]);
+        assert_eq!(program.minimize(&[1]), None);
+
+        // x + y = 5 can not be reached when both are capped at 2
+        let program = IntegerProgram::new(Matrix::from_rows([[1, 1]]), &[5], &[2, 2]);
+        assert_eq!(program.minimize(&[1, 1]), None);
+
+        // Inconsistent system
+        let program = IntegerProgram::new(Matrix::from_rows([[1, 1], [1, 1]]), &[1, 2], &[2, 2]);
+        assert_eq!(program.minimize(&[1, 1]), None);
+    }
+}
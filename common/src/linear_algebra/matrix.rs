@@ -0,0 +1,359 @@
+use super::Rational;
+use std::fmt::Display;
+use std::ops::{Index, IndexMut, Mul};
+
+/// Dense row-major matrix over exact rationals.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Matrix {
+    rows: usize,
+    cols: usize,
+    data: Vec<Rational>,
+}
+
+/// Complete solution space of `A * x = b`: every solution is `particular` plus any linear
+/// combination of the `null_space` vectors.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Solution {
+    pub particular: Vec<Rational>,
+    pub null_space: Vec<Vec<Rational>>,
+    /// Columns without a pivot whose values can be chosen freely, one per null space vector.
+    pub free_variables: Vec<usize>,
+}
+
+impl Solution {
+    pub fn is_unique(&self) -> bool {
+        self.null_space.is_empty()
+    }
+
+    /// Solution obtained by weighting the null space vectors with `params`.
+    pub fn at(&self, params: &[Rational]) -> Vec<Rational> {
+        assert_eq!(params.len(), self.null_space.len());
+
+        let mut x = self.particular.clone();
+        for (param, basis) in params.iter().zip(self.null_space.iter()) {
+            for (xi, bi) in x.iter_mut().zip(basis.iter()) {
+                *xi += *param * *bi;
+            }
+        }
+        x
+    }
+}
+
+impl Matrix {
+    pub fn zeros(rows: usize, cols: usize) -> Self {
+        Self {
+            rows,
+            cols,
+            data: vec![Rational::ZERO; rows * cols],
+        }
+    }
+
+    pub fn identity(n: usize) -> Self {
+        let mut m = Self::zeros(n, n);
+        for i in 0..n {
+            m[(i, i)] = Rational::ONE;
+        }
+        m
+    }
+
+    /// Builds a matrix from its rows, panics if the rows differ in length.
+    pub fn from_rows<R, T>(rows: impl IntoIterator<Item = R>) -> Self
+    where
+        R: IntoIterator<Item = T>,
+        T: Into<Rational>,
+    {
+        let mut data = Vec::new();
+        let mut row_count = 0;
+        let mut cols = None;
+
+        for row in rows {
+            let len_before = data.len();
+            data.extend(row.into_iter().map(Into::into));
+            let len = data.len() - len_before;
+            assert_eq!(
+                *cols.get_or_insert(len),
+                len,
+                "Matrix rows differ in length"
+            );
+            row_count += 1;
+        }
+
+        Self {
+            rows: row_count,
+            cols: cols.unwrap_or(0),
+            data,
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn row(&self, row: usize) -> &[Rational] {
+        &self.data[row * self.cols..(row + 1) * self.cols]
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut t = Self::zeros(self.cols, self.rows);
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                t[(col, row)] = self[(row, col)];
+            }
+        }
+        t
+    }
+
+    /// Appends `column` as an additional rightmost column.
+    pub fn augment(&self, column: &[Rational]) -> Self {
+        assert_eq!(column.len(), self.rows);
+
+        let mut data = Vec::with_capacity(self.rows * (self.cols + 1));
+        for (row, value) in column.iter().enumerate() {
+            data.extend_from_slice(self.row(row));
+            data.push(*value);
+        }
+
+        Self {
+            rows: self.rows,
+            cols: self.cols + 1,
+            data,
+        }
+    }
+
+    pub fn mul_vec(&self, v: &[Rational]) -> Vec<Rational> {
+        assert_eq!(v.len(), self.cols);
+
+        (0..self.rows)
+            .map(|row| self.row(row).iter().zip(v).map(|(a, b)| *a * *b).sum())
+            .collect()
+    }
+
+    fn swap_rows(&mut self, a: usize, b: usize) {
+        if a != b {
+            for col in 0..self.cols {
+                self.data.swap(a * self.cols + col, b * self.cols + col);
+            }
+        }
+    }
+
+    /// Reduced row echelon form via Gauss-Jordan elimination, together with the pivot columns.
+    pub fn rref(&self) -> (Matrix, Vec<usize>) {
+        let mut m = self.clone();
+        let mut pivots = Vec::new();
+        let mut pivot_row = 0;
+
+        for col in 0..m.cols {
+            if pivot_row == m.rows {
+                break;
+            }
+            let Some(row) = (pivot_row..m.rows).find(|row| !m[(*row, col)].is_zero()) else {
+                continue;
+            };
+            m.swap_rows(row, pivot_row);
+
+            let pivot = m[(pivot_row, col)];
+            for c in col..m.cols {
+                m[(pivot_row, c)] /= pivot;
+            }
+
+            for row in (0..m.rows).filter(|row| *row != pivot_row) {
+                let factor = m[(row, col)];
+                if factor.is_zero() {
+                    continue;
+                }
+                for c in col..m.cols {
+                    let delta = factor * m[(pivot_row, c)];
+                    m[(row, c)] -= delta;
+                }
+            }
+
+            pivots.push(col);
+            pivot_row += 1;
+        }
+
+        (m, pivots)
+    }
+
+    pub fn rank(&self) -> usize {
+        self.rref().1.len()
+    }
+
+    /// Basis of all vectors `x` with `A * x = 0`.
+    pub fn null_space(&self) -> Vec<Vec<Rational>> {
+        let (reduced, pivots) = self.rref();
+        Self::null_space_from_rref(&reduced, &pivots, self.cols).0
+    }
+
+    fn null_space_from_rref(
+        reduced: &Matrix,
+        pivots: &[usize],
+        cols: usize,
+    ) -> (Vec<Vec<Rational>>, Vec<usize>) {
+        let free_variables = (0..cols)
+            .filter(|col| !pivots.contains(col))
+            .collect::<Vec<_>>();
+
+        let basis = free_variables
+            .iter()
+            .map(|free| {
+                let mut v = vec![Rational::ZERO; cols];
+                v[*free] = Rational::ONE;
+                for (row, pivot) in pivots.iter().enumerate() {
+                    v[*pivot] = -reduced[(row, *free)];
+                }
+                v
+            })
+            .collect();
+
+        (basis, free_variables)
+    }
+
+    /// Solves `A * x = rhs`, `None` if the system is inconsistent.
+    pub fn solve(&self, rhs: &[Rational]) -> Option<Solution> {
+        let (reduced, pivots) = self.augment(rhs).rref();
+
+        // A pivot in the augmented column means a row reads `0 = 1`
+        if pivots.last() == Some(&self.cols) {
+            return None;
+        }
+
+        let mut particular = vec![Rational::ZERO; self.cols];
+        for (row, pivot) in pivots.iter().enumerate() {
+            particular[*pivot] = reduced[(row, self.cols)];
+        }
+
+        let (null_space, free_variables) = Self::null_space_from_rref(&reduced, &pivots, self.cols);
+
+        Some(Solution {
+            particular,
+            null_space,
+            free_variables,
+        })
+    }
+}
+
+impl Index<(usize, usize)> for Matrix {
+    type Output = Rational;
+
+    fn index(&self, (row, col): (usize, usize)) -> &Self::Output {
+        &self.data[row * self.cols + col]
+    }
+}
+
+impl IndexMut<(usize, usize)> for Matrix {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut Self::Output {
+        &mut self.data[row * self.cols + col]
+    }
+}
+
+impl Mul for &Matrix {
+    type Output = Matrix;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        assert_eq!(self.cols, rhs.rows);
+
+        let mut product = Matrix::zeros(self.rows, rhs.cols);
+        for row in 0..self.rows {
+            for col in 0..rhs.cols {
+                product[(row, col)] = (0..self.cols).map(|k| self[(row, k)] * rhs[(k, col)]).sum();
+            }
+        }
+        product
+    }
+}
+
+impl Display for Matrix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in 0..self.rows {
+            let line = self
+                .row(row)
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<_>>()
+                .join(" ");
+            writeln!(f, "[{line}]")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn vector(values: &[i128]) -> Vec<Rational> {
+        values.iter().map(|v| Rational::from(*v)).collect()
+    }
+
+    #[test]
+    fn rref_and_rank() {
+        let m = Matrix::from_rows([[1, 2, 3], [2, 4, 6], [1, 0, 1]]);
+        let (reduced, pivots) = m.rref();
+        assert_eq!(pivots, vec![0, 1]);
+        assert_eq!(
+            reduced,
+            Matrix::from_rows([[1, 0, 1], [0, 1, 1], [0, 0, 0]])
+        );
+        assert_eq!(m.rank(), 2);
+        assert_eq!(Matrix::identity(4).rank(), 4);
+    }
+
+    #[test]
+    fn null_space() {
+        let m = Matrix::from_rows([[1, 2, 3], [2, 4, 6], [1, 0, 1]]);
+        let basis = m.null_space();
+        assert_eq!(basis, vec![vector(&[-1, -1, 1])]);
+        for v in basis {
+            assert!(m.mul_vec(&v).iter().all(Rational::is_zero));
+        }
+    }
+
+    #[test]
+    fn solve_unique() {
+        // 94a + 22b = 8400, 34a + 67b = 5400
+        let m = Matrix::from_rows([[94, 22], [34, 67]]);
+        let solution = m.solve(&vector(&[8400, 5400])).unwrap();
+        assert!(solution.is_unique());
+        assert_eq!(solution.particular, vector(&[80, 40]));
+
+        let m = Matrix::from_rows([[2, 1], [1, 3]]);
+        let solution = m.solve(&vector(&[1, 1])).unwrap();
+        assert_eq!(
+            solution.particular,
+            vec![Rational::new(2, 5), Rational::new(1, 5)]
+        );
+    }
+
+    #[test]
+    fn solve_underdetermined() {
+        let m = Matrix::from_rows([[1, 1, 0], [0, 1, 1]]);
+        let b = vector(&[3, 5]);
+        let solution = m.solve(&b).unwrap();
+        assert_eq!(solution.free_variables, vec![2]);
+        assert_eq!(solution.particular, vector(&[-2, 5, 0]));
+
+        for t in -3..3 {
+            let x = solution.at(&[Rational::from(t)]);
+            assert_eq!(m.mul_vec(&x), b);
+        }
+    }
+
+    #[test]
+    fn solve_inconsistent() {
+        let m = Matrix::from_rows([[1, 1], [2, 2]]);
+        assert!(m.solve(&vector(&[1, 3])).is_none());
+    }
+
+    #[test]
+    fn product() {
+        let a = Matrix::from_rows([[1, 2], [3, 4]]);
+        let b = Matrix::from_rows([[0, 1], [1, 0]]);
+        assert_eq!(&a * &b, Matrix::from_rows([[2, 1], [4, 3]]));
+        assert_eq!(&a * &Matrix::identity(2), a);
+        assert_eq!(a.transpose(), Matrix::from_rows([[1, 3], [2, 4]]));
+    }
+}
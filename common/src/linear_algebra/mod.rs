@@ -0,0 +1,9 @@
+mod gf2;
+mod integer_program;
+mod matrix;
+mod rational;
+
+pub use gf2::{BitVector, Gf2Matrix, Gf2Solution};
+pub use integer_program::IntegerProgram;
+pub use matrix::{Matrix, Solution};
+pub use rational::Rational;
@@ -0,0 +1,257 @@
+use crate::number_theory::gcd;
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+/// Exact fraction `num / den` that is always kept in lowest terms with a positive denominator.
+///
+/// Arithmetic cancels common factors before multiplying and panics instead of silently wrapping
+/// if an intermediate value still does not fit into an `i128`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rational {
+    num: i128,
+    den: i128,
+}
+
+impl Rational {
+    pub const ZERO: Rational = Rational { num: 0, den: 1 };
+    pub const ONE: Rational = Rational { num: 1, den: 1 };
+
+    pub fn new(num: i128, den: i128) -> Self {
+        assert!(den != 0, "Denominator of a rational must not be zero");
+
+        let divisor = gcd(num, den);
+        let sign = den.signum();
+        Self {
+            num: sign * num / divisor,
+            den: sign * den / divisor,
+        }
+    }
+
+    pub fn numer(&self) -> i128 {
+        self.num
+    }
+
+    pub fn denom(&self) -> i128 {
+        self.den
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.num == 0
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.den == 1
+    }
+
+    /// The value as integer if it has no fractional part.
+    pub fn to_integer(&self) -> Option<i128> {
+        self.is_integer().then_some(self.num)
+    }
+
+    pub fn floor(&self) -> i128 {
+        self.num.div_euclid(self.den)
+    }
+
+    pub fn ceil(&self) -> i128 {
+        -(-self.num).div_euclid(self.den)
+    }
+
+    pub fn abs(&self) -> Self {
+        Self {
+            num: self.num.abs(),
+            den: self.den,
+        }
+    }
+
+    pub fn recip(&self) -> Self {
+        Self::new(self.den, self.num)
+    }
+}
+
+fn overflow() -> ! {
+    panic!("Rational arithmetic overflowed i128");
+}
+
+impl Default for Rational {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+impl From<i128> for Rational {
+    fn from(value: i128) -> Self {
+        Self { num: value, den: 1 }
+    }
+}
+
+macro_rules! impl_from_int {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Rational {
+                fn from(value: $t) -> Self {
+                    Self::from(value as i128)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_int!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl Display for Rational {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Both denominators are positive, so cross multiplying keeps the order
+        let lhs = self.num.checked_mul(other.den);
+        let rhs = other.num.checked_mul(self.den);
+        match (lhs, rhs) {
+            (Some(lhs), Some(rhs)) => lhs.cmp(&rhs),
+            _ => (*self - *other).num.cmp(&0),
+        }
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for Rational {
+    type Output = Rational;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let divisor = gcd(self.den, rhs.den);
+        let lhs_factor = rhs.den / divisor;
+        let rhs_factor = self.den / divisor;
+
+        let num = self
+            .num
+            .checked_mul(lhs_factor)
+            .zip(rhs.num.checked_mul(rhs_factor))
+            .and_then(|(a, b)| a.checked_add(b))
+            .unwrap_or_else(|| overflow());
+        let den = self
+            .den
+            .checked_mul(lhs_factor)
+            .unwrap_or_else(|| overflow());
+        Self::new(num, den)
+    }
+}
+
+impl Sub for Rational {
+    type Output = Rational;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + -rhs
+    }
+}
+
+impl Mul for Rational {
+    type Output = Rational;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        // Cancel crosswise first to keep the intermediate products small
+        let a = gcd(self.num, rhs.den);
+        let b = gcd(rhs.num, self.den);
+
+        let num = (self.num / a)
+            .checked_mul(rhs.num / b)
+            .unwrap_or_else(|| overflow());
+        let den = (self.den / b)
+            .checked_mul(rhs.den / a)
+            .unwrap_or_else(|| overflow());
+        Self::new(num, den)
+    }
+}
+
+impl Div for Rational {
+    type Output = Rational;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn div(self, rhs: Self) -> Self::Output {
+        self * rhs.recip()
+    }
+}
+
+impl Neg for Rational {
+    type Output = Rational;
+
+    fn neg(self) -> Self::Output {
+        Self {
+            num: self.num.checked_neg().unwrap_or_else(|| overflow()),
+            den: self.den,
+        }
+    }
+}
+
+macro_rules! impl_assign {
+    ($($trait:ident $method:ident $op:tt),*) => {
+        $(
+            impl $trait for Rational {
+                fn $method(&mut self, rhs: Self) {
+                    *self = *self $op rhs;
+                }
+            }
+        )*
+    };
+}
+
+impl_assign!(AddAssign add_assign +, SubAssign sub_assign -, MulAssign mul_assign *, DivAssign div_assign /);
+
+impl std::iter::Sum for Rational {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, |acc, r| acc + r)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn normalization() {
+        assert_eq!(Rational::new(6, -8), Rational::new(-3, 4));
+        assert_eq!(Rational::new(0, -5), Rational::ZERO);
+        assert_eq!(Rational::new(-3, 4).to_string(), "-3/4");
+        assert_eq!(Rational::from(7).to_string(), "7");
+    }
+
+    #[test]
+    fn arithmetic() {
+        let a = Rational::new(1, 6);
+        let b = Rational::new(3, 10);
+        assert_eq!(a + b, Rational::new(7, 15));
+        assert_eq!(a - b, Rational::new(-2, 15));
+        assert_eq!(a * b, Rational::new(1, 20));
+        assert_eq!(a / b, Rational::new(5, 9));
+        assert!(a < b);
+        assert_eq!(Rational::new(-7, 2).floor(), -4);
+        assert_eq!(Rational::new(-7, 2).ceil(), -3);
+        assert_eq!(Rational::new(7, 2).floor(), 3);
+        assert_eq!(Rational::new(7, 2).ceil(), 4);
+    }
+
+    #[test]
+    fn large_values_cancel() {
+        let big = Rational::new(i128::MAX / 3, 7);
+        assert_eq!(big * Rational::new(7, i128::MAX / 3), Rational::ONE);
+        assert_eq!(big / big, Rational::ONE);
+    }
+
+    #[test]
+    #[should_panic(expected = "overflowed")]
+    fn overflow_panics() {
+        let _ = Rational::from(i128::MAX) + Rational::ONE;
+    }
+}
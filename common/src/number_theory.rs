@@ -0,0 +1,165 @@
+/// Greatest common divisor, always non-negative and `gcd(0, 0) == 0`.
+pub fn gcd(mut a: i128, mut b: i128) -> i128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.abs()
+}
+
+/// Least common multiple, always non-negative. Panics if the result does not fit into an `i128`.
+pub fn lcm(a: i128, b: i128) -> i128 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    (a / gcd(a, b))
+        .checked_mul(b)
+        .map(i128::abs)
+        .expect("Least common multiple overflowed i128")
+}
+
+/// Extended Euclidean algorithm, returns `(g, x, y)` with `a * x + b * y = g = gcd(a, b)`.
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+        (old_y, y) = (y, old_y - q * y);
+    }
+
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+/// `x` in `0..m` with `a * x = 1 (mod m)`, `None` if `a` and `m` are not coprime.
+pub fn mod_inverse(a: i128, m: i128) -> Option<i128> {
+    assert!(m > 0, "Modulus must be positive");
+
+    let (g, x, _) = extended_gcd(a.rem_euclid(m), m);
+    (g == 1).then(|| x.rem_euclid(m))
+}
+
+/// `a * b mod m` in `0..m` without overflowing for any modulus that fits into an `i128`.
+pub fn mul_mod(a: i128, b: i128, m: i128) -> i128 {
+    assert!(m > 0, "Modulus must be positive");
+
+    let m = m as u128;
+    let (mut a, mut b) = (
+        a.rem_euclid(m as i128) as u128,
+        b.rem_euclid(m as i128) as u128,
+    );
+    if let Some(product) = a.checked_mul(b) {
+        return (product % m) as i128;
+    }
+
+    // Double and add, both operands stay below `m < 2^127` so their sum fits into an `u128`
+    let mut result = 0;
+    while b > 0 {
+        if b & 1 == 1 {
+            result = (result + a) % m;
+        }
+        a = (a << 1) % m;
+        b >>= 1;
+    }
+    result as i128
+}
+
+/// `base^exp mod m` in `0..m` by square and multiply.
+pub fn mod_pow(base: i128, mut exp: u128, m: i128) -> i128 {
+    assert!(m > 0, "Modulus must be positive");
+
+    let mut base = base.rem_euclid(m);
+    let mut result = 1 % m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+/// Solves the system `x = r (mod m)` for all `(r, m)` in `congruences`.
+///
+/// The moduli do not need to be pairwise coprime. Returns the smallest non-negative solution
+/// together with the lcm of all moduli, every other solution differs from it by a multiple of
+/// that lcm. `None` if the congruences contradict each other.
+pub fn crt(congruences: &[(i128, i128)]) -> Option<(i128, i128)> {
+    let mut x = 0;
+    let mut modulus = 1;
+
+    for (r, m) in congruences {
+        assert!(*m > 0, "Modulus must be positive");
+
+        // Find k with x + k * modulus = r (mod m), which requires gcd(modulus, m) | r - x
+        let (g, p, _) = extended_gcd(modulus, *m);
+        let diff = (r - x).rem_euclid(*m);
+        if diff % g != 0 {
+            return None;
+        }
+
+        let step = m / g;
+        let k = mul_mod(diff / g, p, step);
+        let combined = lcm(modulus, *m);
+        x = (x + mul_mod(k, modulus, combined)).rem_euclid(combined);
+        modulus = combined;
+    }
+
+    Some((x, modulus))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn gcd_and_lcm() {
+        assert_eq!(gcd(12, 18), 6);
+        assert_eq!(gcd(-12, 18), 6);
+        assert_eq!(gcd(0, 0), 0);
+        assert_eq!(lcm(4, 6), 12);
+        assert_eq!(lcm(-4, 6), 12);
+        assert_eq!(lcm(0, 6), 0);
+    }
+
+    #[test]
+    fn extended_euclid() {
+        for (a, b) in [(240, 46), (-7, 3), (0, 5), (17, 0), (94, 22)] {
+            let (g, x, y) = extended_gcd(a, b);
+            assert_eq!(g, gcd(a, b));
+            assert_eq!(a * x + b * y, g);
+        }
+    }
+
+    #[test]
+    fn inverse_and_pow() {
+        assert_eq!(mod_inverse(3, 11), Some(4));
+        assert_eq!(mod_inverse(-3, 11), Some(7));
+        assert_eq!(mod_inverse(6, 9), None);
+        assert_eq!(mod_pow(4, 13, 497), 445);
+        assert_eq!(mod_pow(2, 0, 1), 0);
+
+        // Fermat's little theorem on the Mersenne prime 2^127 - 1
+        let p = i128::MAX;
+        assert_eq!(mod_pow(3, (p - 1) as u128, p), 1);
+        assert_eq!(mul_mod(p - 1, p - 1, p), 1);
+    }
+
+    #[test]
+    fn chinese_remainder() {
+        assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        assert_eq!(crt(&[(-1, 101), (-2, 103)]), Some((5251, 10403)));
+
+        // Non-coprime moduli
+        assert_eq!(crt(&[(2, 4), (4, 6)]), Some((10, 12)));
+        assert_eq!(crt(&[(1, 4), (2, 6)]), None);
+        assert_eq!(crt(&[]), Some((0, 1)));
+    }
+}
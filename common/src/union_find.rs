@@ -0,0 +1,121 @@
+/// Disjoint-set forest over the elements `0..len` with path compression and union by size.
+#[derive(Clone, Debug)]
+pub struct UnionFind {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+    components: usize,
+}
+
+impl UnionFind {
+    pub fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+            size: vec![1; len],
+            components: len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    /// Representative of the component containing `x`.
+    pub fn find(&mut self, mut x: usize) -> usize {
+        // Path halving: every visited node is linked to its grandparent
+        while self.parent[x] != x {
+            self.parent[x] = self.parent[self.parent[x]];
+            x = self.parent[x];
+        }
+        x
+    }
+
+    /// Merges the components of `a` and `b`, returns `false` if they already were the same.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+
+        // Attach the smaller tree below the root of the bigger one
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+        self.components -= 1;
+        true
+    }
+
+    pub fn connected(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// Number of elements in the component containing `x`.
+    pub fn component_size(&mut self, x: usize) -> usize {
+        let root = self.find(x);
+        self.size[root]
+    }
+
+    /// Number of disjoint components.
+    pub fn component_count(&self) -> usize {
+        self.components
+    }
+
+    /// All components with their elements in ascending order, ordered by their smallest element.
+    pub fn components(&mut self) -> Vec<Vec<usize>> {
+        let mut index_of_root = vec![usize::MAX; self.len()];
+        let mut components = Vec::with_capacity(self.components);
+
+        for x in 0..self.len() {
+            let root = self.find(x);
+            if index_of_root[root] == usize::MAX {
+                index_of_root[root] = components.len();
+                components.push(Vec::with_capacity(self.size[root]));
+            }
+            components[index_of_root[root]].push(x);
+        }
+
+        components
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn union_and_find() {
+        let mut uf = UnionFind::new(8);
+        assert_eq!(uf.component_count(), 8);
+
+        assert!(uf.union(0, 1));
+        assert!(uf.union(2, 3));
+        assert!(uf.union(1, 3));
+        assert!(!uf.union(0, 2));
+        assert!(uf.union(5, 6));
+
+        assert!(uf.connected(0, 3));
+        assert!(!uf.connected(0, 4));
+        assert_eq!(uf.component_size(2), 4);
+        assert_eq!(uf.component_size(4), 1);
+        assert_eq!(uf.component_size(6), 2);
+        assert_eq!(uf.component_count(), 4);
+    }
+
+    #[test]
+    fn components() {
+        let mut uf = UnionFind::new(7);
+        uf.union(6, 0);
+        uf.union(3, 4);
+        uf.union(4, 1);
+
+        assert_eq!(
+            uf.components(),
+            vec![vec![0, 6], vec![1, 3, 4], vec![2], vec![5]]
+        );
+    }
+}